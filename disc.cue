@@ -0,0 +1,14 @@
+REM Data track from mode1.bin, two audio tracks after it
+CATALOG 0000000000000
+FILE "mode1.bin" BINARY
+  TRACK 01 MODE1/2352
+    INDEX 01 00:00:00
+FILE "audio.bin" BINARY
+  TRACK 02 AUDIO
+    FLAGS DCP
+    INDEX 00 00:00:00
+    INDEX 01 00:00:10
+  TRACK 03 AUDIO
+    PREGAP 00:00:05
+    INDEX 01 00:00:40
+    POSTGAP 00:00:02
@@ -6,35 +6,55 @@ use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 
 use fuser::{ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, Request};
-use libc::{EISDIR, ENOTDIR};
+use libc::{EINVAL, EISDIR, ENOTDIR};
 use std::time::Duration;
 
-use iso9660::{DirectoryEntry, ISODirectory, ISOFileReader, ISO9660};
+use iso9660::{DirectoryEntry, ISODirectory, ISOFileReader, SpecialFileType, ISO9660};
 
 fn entry_to_filetype(entry: &DirectoryEntry<File>) -> fuser::FileType {
     match entry {
         DirectoryEntry::File(_) => fuser::FileType::RegularFile,
         DirectoryEntry::Directory(_) => fuser::FileType::Directory,
+        DirectoryEntry::Symlink(_) => fuser::FileType::Symlink,
+        DirectoryEntry::Special(special) => match special.file_type() {
+            SpecialFileType::CharacterDevice => fuser::FileType::CharDevice,
+            SpecialFileType::BlockDevice => fuser::FileType::BlockDevice,
+            SpecialFileType::Fifo => fuser::FileType::NamedPipe,
+            SpecialFileType::Socket => fuser::FileType::Socket,
+        },
     }
 }
 
 fn get_fileattr(ino: u64, entry: &DirectoryEntry<File>) -> fuser::FileAttr {
-    let blocks = (entry.header().extent_length + 2048 - 1) / 2048; // ceil(len / 2048
-    let time = entry.header().time.into();
+    let size = match entry {
+        DirectoryEntry::File(file) => file.size(),
+        _ => entry.header().extent_length as u64,
+    };
+    let blocks = size.div_ceil(2048);
+    let time = entry.header().time;
+    let timestamps = entry.timestamps();
+    let metadata = entry.metadata();
+    let rdev = match entry {
+        DirectoryEntry::Special(special) => match special.device() {
+            Some((major, minor)) => libc::makedev(major, minor) as u32,
+            None => 0,
+        },
+        _ => 0,
+    };
     fuser::FileAttr {
         ino,
-        size: entry.header().extent_length as u64,
-        blocks: blocks as u64,
-        atime: time,
-        mtime: time,
-        ctime: time,
-        crtime: time,
-        kind: entry_to_filetype(&entry),
-        perm: 0444,
-        nlink: 1,
-        uid: 0,
-        gid: 0,
-        rdev: 0,
+        size,
+        blocks,
+        atime: timestamps.access.unwrap_or(time).into(),
+        mtime: timestamps.modify.unwrap_or(time).into(),
+        ctime: timestamps.attributes.unwrap_or(time).into(),
+        crtime: timestamps.creation.unwrap_or(time).into(),
+        kind: entry_to_filetype(entry),
+        perm: metadata.permissions() as u16,
+        nlink: metadata.nlink,
+        uid: metadata.uid,
+        gid: metadata.gid,
+        rdev,
         flags: 0,
         blksize: 512,
     }
@@ -101,6 +121,15 @@ impl fuser::Filesystem for ISOFuse {
         reply.attr(&Duration::from_secs(0), &fileattr);
     }
 
+    fn readlink(&mut self, _req: &Request, ino: u64, reply: ReplyData) {
+        let entry = self.inodes.get(&ino).unwrap();
+        if let DirectoryEntry::Symlink(link) = entry {
+            reply.data(link.target().as_bytes());
+        } else {
+            reply.error(EINVAL);
+        }
+    }
+
     fn open(&mut self, _req: &Request, ino: u64, _flags: i32, reply: ReplyOpen) {
         let entry = self.inodes.get(&ino).unwrap();
         if let DirectoryEntry::File(file) = entry {
@@ -180,18 +209,20 @@ impl fuser::Filesystem for ISOFuse {
                 .read_entry_at(&mut block, &mut block_num, offset)
                 .unwrap();
 
-            let kind = entry_to_filetype(&entry);
-            if reply.add(
-                self.inode_number,
-                next_offset.map(|x| x as i64).unwrap_or(-1),
-                kind,
-                entry.identifier(),
-            ) {
-                break;
-            }
+            if !entry.is_relocated() {
+                let kind = entry_to_filetype(&entry);
+                if reply.add(
+                    self.inode_number,
+                    next_offset.map(|x| x as i64).unwrap_or(-1),
+                    kind,
+                    entry.identifier(),
+                ) {
+                    break;
+                }
 
-            self.inodes.insert(self.inode_number, entry);
-            self.inode_number += 1;
+                self.inodes.insert(self.inode_number, entry);
+                self.inode_number += 1;
+            }
 
             if let Some(next_offset) = next_offset {
                 offset = next_offset;
@@ -27,7 +27,7 @@ fn main() {
             Some(DirectoryEntry::Directory(dir)) => {
                 print_tree(&dir, 0);
             }
-            Some(DirectoryEntry::File(_)) => {
+            Some(_) => {
                 eprintln!("'{}' is not a directory", dirpath);
                 process::exit(1);
             }
@@ -60,6 +60,18 @@ fn print_tree<T: ISO9660Reader>(dir: &ISODirectory<T>, level: u32) {
                 }
                 println!("- {}", file.identifier);
             }
+            DirectoryEntry::Symlink(link) => {
+                for _i in 0..level {
+                    print!("  ");
+                }
+                println!("- {} -> {}", link.identifier, link.target());
+            }
+            DirectoryEntry::Special(special) => {
+                for _i in 0..level {
+                    print!("  ");
+                }
+                println!("- {} ({:?})", special.identifier, special.file_type());
+            }
         }
     }
 }
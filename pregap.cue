@@ -0,0 +1,5 @@
+REM Data track from mode1.bin, after a stored pregap
+FILE "pregap.bin" BINARY
+  TRACK 01 MODE1/2352
+    INDEX 00 00:00:00
+    INDEX 01 00:02:00
@@ -0,0 +1,9 @@
+REM Enhanced CD: audio in session 1, data in session 2
+REM SESSION 01
+FILE "audio.bin" BINARY
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+REM SESSION 02
+FILE "session2.bin" BINARY
+  TRACK 02 MODE1/2352
+    INDEX 01 00:00:00
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use std::cmp::min;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{ISOError, Result, Track};
+
+// CD-DA is 16-bit stereo PCM at 44.1 kHz, in sectors of 2352 bytes
+const SAMPLE_RATE: u32 = 44100;
+const CHANNELS: u16 = 2;
+const BITS_PER_SAMPLE: u16 = 16;
+const AUDIO_SECTOR_SIZE: u64 = 2352;
+const WAV_HEADER_SIZE: u64 = 44;
+
+/// Reads an audio track of a disc image as a WAV file, with its PCM data
+/// following a synthesized header
+#[derive(Debug)]
+pub struct WavReader<T: Read + Seek> {
+    inner: T,
+    header: [u8; WAV_HEADER_SIZE as usize],
+    /// Byte offset in `inner` of the first stored sector that is read
+    data_offset: u64,
+    /// Size of each stored sector, which may be followed by subchannel data
+    sector_size: u64,
+    /// Bytes of silence before the stored sectors, for a pregap that is not
+    /// stored in the image
+    silence: u64,
+    size: u64,
+    seek: u64,
+}
+
+impl<T: Read + Seek> WavReader<T> {
+    /// Read `track` from the file it is stored in. With `pregap`, the track
+    /// starts at its pregap rather than at index 1, as audio CD rippers
+    /// do for hidden tracks.
+    pub fn new(inner: T, track: &Track, pregap: bool) -> Result<WavReader<T>> {
+        if track.mode.is_data() {
+            return Err(ISOError::InvalidFs("Not an audio track"));
+        }
+        let sector_size = track.mode.sector_size() as u64;
+
+        let (stored_pregap, silence) = if pregap {
+            (track.stored_pregap, track.pregap - track.stored_pregap)
+        } else {
+            (0, 0)
+        };
+        let pcm_size = (silence + stored_pregap + track.length) * AUDIO_SECTOR_SIZE;
+
+        Ok(WavReader {
+            inner,
+            header: wav_header(pcm_size as u32),
+            data_offset: track.offset - stored_pregap * sector_size,
+            sector_size,
+            silence: silence * AUDIO_SECTOR_SIZE,
+            size: WAV_HEADER_SIZE + pcm_size,
+            seek: 0,
+        })
+    }
+
+    /// Size of the WAV file, including its header
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+fn wav_header(pcm_size: u32) -> [u8; WAV_HEADER_SIZE as usize] {
+    let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+    let mut header = Vec::with_capacity(WAV_HEADER_SIZE as usize);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(pcm_size + WAV_HEADER_SIZE as u32 - 8).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    // Uncompressed PCM
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&CHANNELS.to_le_bytes());
+    header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    header.extend_from_slice(&(SAMPLE_RATE * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&pcm_size.to_le_bytes());
+
+    let mut array = [0; WAV_HEADER_SIZE as usize];
+    array.copy_from_slice(&header);
+    array
+}
+
+impl<T: Read + Seek> Read for WavReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.seek >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let count = if self.seek < WAV_HEADER_SIZE {
+            let header = &self.header[self.seek as usize..];
+            let count = min(header.len(), buf.len());
+            buf[..count].copy_from_slice(&header[..count]);
+            count
+        } else if self.seek - WAV_HEADER_SIZE < self.silence {
+            let count = min(
+                self.silence - (self.seek - WAV_HEADER_SIZE),
+                buf.len() as u64,
+            );
+            buf[..count as usize].fill(0);
+            count as usize
+        } else {
+            // Read up to the end of the sector, skipping any subchannel data
+            let pos = self.seek - WAV_HEADER_SIZE - self.silence;
+            let in_sector = pos % AUDIO_SECTOR_SIZE;
+            let count = min(AUDIO_SECTOR_SIZE - in_sector, buf.len() as u64) as usize;
+            let offset = self.data_offset + pos / AUDIO_SECTOR_SIZE * self.sector_size + in_sector;
+            self.inner.seek(SeekFrom::Start(offset))?;
+            self.inner.read_exact(&mut buf[..count])?;
+            count
+        };
+
+        self.seek += count as u64;
+        Ok(count)
+    }
+}
+
+impl<T: Read + Seek> Seek for WavReader<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let seek = match pos {
+            SeekFrom::Start(pos) => pos as i64,
+            SeekFrom::End(pos) => self.size as i64 + pos,
+            SeekFrom::Current(pos) => self.seek as i64 + pos,
+        };
+
+        if seek < 0 {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek"))
+        } else {
+            self.seek = seek as u64;
+            Ok(seek as u64)
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+//! CloneCD CCD files, which describe the table of contents of the raw
+//! sectors stored in an IMG file. The subchannel data of the SUB file isn't
+//! needed to read the tracks.
+
+use std::collections::BTreeMap;
+
+use crate::{ISOError, Result, Track, TrackMode};
+
+// Every sector of the IMG file is stored whole
+const SECTOR_SIZE: u64 = 2352;
+
+/// A track from the `[Entry]` sections of the table of contents, and its
+/// `[TRACK]` section
+#[derive(Default)]
+struct TocTrack {
+    session: u8,
+    data: bool,
+    start: i64,
+    mode: Option<i64>,
+    index0: Option<i64>,
+}
+
+/// Read the tracks from the text of a CCD file, given the size of the IMG
+/// file
+pub(crate) fn read_tracks(text: &str, img_size: u64) -> Result<Vec<Track>> {
+    let mut toc = BTreeMap::<u8, TocTrack>::new();
+    // Start of the lead-out of each session, where its last track ends
+    let mut lead_outs = BTreeMap::new();
+
+    let mut section = String::new();
+    let mut entry = BTreeMap::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            if section.starts_with("ENTRY ") {
+                add_entry(&entry, &mut toc, &mut lead_outs)?;
+            }
+            section = line
+                .trim_matches(|x| x == '[' || x == ']')
+                .to_ascii_uppercase();
+            entry.clear();
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim().to_ascii_uppercase(), value.trim()),
+            None => continue,
+        };
+
+        if section.starts_with("ENTRY ") {
+            entry.insert(key, int(value)?);
+        } else if let Some(number) = section.strip_prefix("TRACK ") {
+            let track = toc.entry(number.trim().parse()?).or_default();
+            match key.as_str() {
+                "MODE" => track.mode = Some(int(value)?),
+                "INDEX 0" => track.index0 = Some(int(value)?),
+                _ => {}
+            }
+        }
+    }
+    if section.starts_with("ENTRY ") {
+        add_entry(&entry, &mut toc, &mut lead_outs)?;
+    }
+
+    let numbers = toc.keys().copied().collect::<Vec<_>>();
+    let mut tracks = Vec::new();
+    let mut offset = 0;
+    for (i, number) in numbers.iter().enumerate() {
+        let track = &toc[number];
+        let mode = match track.mode {
+            Some(0) => TrackMode::Audio,
+            Some(1) => TrackMode::Mode1(SECTOR_SIZE as u16),
+            Some(2) => TrackMode::Mode2(SECTOR_SIZE as u16),
+            None if !track.data => TrackMode::Audio,
+            None => TrackMode::Mode1(SECTOR_SIZE as u16),
+            Some(_) => return Err(ISOError::InvalidFs("Unknown CCD track mode")),
+        };
+        let first = track.index0.unwrap_or(track.start).min(track.start);
+
+        // Tracks end where the next track of the session begins, or at the
+        // lead-out. The lead-out and lead-in between sessions aren't stored.
+        let end = match numbers.get(i + 1).map(|x| &toc[x]) {
+            Some(next) if next.session == track.session => next.index0.unwrap_or(next.start),
+            _ => match lead_outs.get(&track.session) {
+                Some(&lead_out) => lead_out,
+                None => track.start + (img_size.saturating_sub(offset) / SECTOR_SIZE) as i64,
+            },
+        };
+        if first < 0 || end < track.start {
+            return Err(ISOError::InvalidFs("Invalid CCD track"));
+        }
+
+        let stored_pregap = (track.start - first) as u64;
+        tracks.push(Track {
+            number: *number,
+            session: track.session,
+            mode,
+            file: 0,
+            offset: offset + stored_pregap * SECTOR_SIZE,
+            start: track.start as u64,
+            pregap: stored_pregap,
+            stored_pregap,
+            length: (end - track.start) as u64,
+        });
+        offset += (end - first) as u64 * SECTOR_SIZE;
+    }
+
+    if tracks.is_empty() {
+        return Err(ISOError::InvalidFs("No tracks in CCD file"));
+    }
+    Ok(tracks)
+}
+
+/// Add an `[Entry]` of the table of contents, which describes a track or
+/// the lead-out of a session
+fn add_entry(
+    entry: &BTreeMap<String, i64>,
+    toc: &mut BTreeMap<u8, TocTrack>,
+    lead_outs: &mut BTreeMap<u8, i64>,
+) -> Result<()> {
+    let get = |key: &str| {
+        entry
+            .get(key)
+            .copied()
+            .ok_or(ISOError::InvalidFs("Incomplete CCD entry"))
+    };
+    let session = get("SESSION")? as u8;
+    match get("POINT")? {
+        point @ 1..=99 => {
+            let track = toc.entry(point as u8).or_default();
+            track.session = session;
+            // Bit 2 of the control field is set on data tracks
+            track.data = get("CONTROL")? & 0x04 != 0;
+            track.start = get("PLBA")?;
+        }
+        0xa2 => {
+            lead_outs.insert(session, get("PLBA")?);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Parse a decimal or `0x` prefixed hexadecimal value
+fn int(value: &str) -> Result<i64> {
+    Ok(match value.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16)?,
+        None => value.parse()?,
+    })
+}
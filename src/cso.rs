@@ -0,0 +1,259 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+//! Compressed ISO images in the CISO format: CSO images of deflate
+//! compressed blocks, and ZSO images of LZ4 compressed blocks
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{ISOError, Result};
+
+const HEADER_SIZE: usize = 24;
+// Set on index entries of blocks that are stored uncompressed
+const PLAIN_FLAG: u32 = 0x8000_0000;
+// Number of decompressed blocks kept, so sequential reads of sectors smaller
+// than a block don't decompress it again
+const CACHE_BLOCKS: usize = 16;
+// Limits on the size of the header fields, which are read into memory
+const MAX_BLOCK_SIZE: u32 = 1024 * 1024;
+const MAX_BLOCKS: u64 = 64 * 1024 * 1024;
+
+/// Compression of the blocks of a compressed ISO image
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Raw deflate streams, in CSO images
+    Deflate,
+    /// LZ4 blocks, in ZSO images
+    Lz4,
+}
+
+/// A reader for a CSO or ZSO compressed image, which reads like the
+/// uncompressed image. Blocks are decompressed when they are read.
+#[derive(Debug)]
+pub struct CsoReader<T: Read + Seek> {
+    inner: T,
+    compression: Compression,
+    /// Size of the uncompressed image
+    size: u64,
+    block_size: u32,
+    /// Offsets are stored shifted right by `align` bits
+    align: u8,
+    /// Offset of each block in `inner`, and of the end of the last block
+    index: Vec<u32>,
+    /// Recently read blocks, most recent first
+    cache: VecDeque<(u64, Vec<u8>)>,
+    seek: u64,
+}
+
+impl<T: Read + Seek> CsoReader<T> {
+    /// Read the header and block index of a CSO or ZSO image
+    pub fn new(mut inner: T) -> Result<CsoReader<T>> {
+        let mut header = [0; HEADER_SIZE];
+        inner.seek(SeekFrom::Start(0))?;
+        inner.read_exact(&mut header)?;
+
+        let compression = match &header[..4] {
+            b"CISO" => Compression::Deflate,
+            b"ZISO" => Compression::Lz4,
+            _ => return Err(ISOError::InvalidFs("Not a CSO or ZSO image")),
+        };
+        // Version 2 of CSO mixes compression methods, and isn't supported
+        if header[20] > 1 {
+            return Err(ISOError::InvalidFs("Unsupported CSO version"));
+        }
+        let size = u64::from_le_bytes([
+            header[8], header[9], header[10], header[11], header[12], header[13], header[14],
+            header[15],
+        ]);
+        let block_size = u32::from_le_bytes([header[16], header[17], header[18], header[19]]);
+        let align = header[21];
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE || align > 31 {
+            return Err(ISOError::InvalidFs("Invalid CSO header"));
+        }
+
+        let block_count = size.div_ceil(block_size as u64);
+        if block_count > MAX_BLOCKS {
+            return Err(ISOError::InvalidFs("Invalid CSO header"));
+        }
+        // The index always follows the 24 byte header, whatever the header
+        // size field says
+        let mut index = vec![0; (block_count as usize + 1) * 4];
+        inner.read_exact(&mut index)?;
+        let index = index
+            .chunks_exact(4)
+            .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+            .collect();
+
+        Ok(CsoReader {
+            inner,
+            compression,
+            size,
+            block_size,
+            align,
+            index,
+            cache: VecDeque::new(),
+            seek: 0,
+        })
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Size of the uncompressed image
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// The decompressed block at `block`, from the cache if it was read
+    /// recently
+    fn block(&mut self, block: u64) -> io::Result<&[u8]> {
+        match self.cache.iter().position(|x| x.0 == block) {
+            Some(i) => {
+                let entry = self.cache.remove(i).unwrap();
+                self.cache.push_front(entry);
+            }
+            None => {
+                let data = self.read_block(block)?;
+                if self.cache.len() == CACHE_BLOCKS {
+                    self.cache.pop_back();
+                }
+                self.cache.push_front((block, data));
+            }
+        }
+        Ok(&self.cache[0].1)
+    }
+
+    fn read_block(&mut self, block: u64) -> io::Result<Vec<u8>> {
+        let entry = self.index[block as usize];
+        let start = ((entry & !PLAIN_FLAG) as u64) << self.align;
+        let end = ((self.index[block as usize + 1] & !PLAIN_FLAG) as u64) << self.align;
+        let len = end
+            .checked_sub(start)
+            .ok_or_else(|| invalid_data("Invalid CSO block index"))?;
+        let block_start = block * self.block_size as u64;
+        let block_len = (self.size - block_start).min(self.block_size as u64) as usize;
+
+        // Blocks may be padded to the alignment, so only `block_len` bytes
+        // of stored blocks are read
+        let mut stored = vec![
+            0;
+            if entry & PLAIN_FLAG != 0 {
+                block_len.min(len as usize)
+            } else {
+                len.min(self.block_size as u64 * 2) as usize
+            }
+        ];
+        self.inner.seek(SeekFrom::Start(start))?;
+        self.inner.read_exact(&mut stored)?;
+        if entry & PLAIN_FLAG != 0 {
+            stored.resize(block_len, 0);
+            return Ok(stored);
+        }
+
+        let mut data = vec![0; self.block_size as usize];
+        let count = match self.compression {
+            Compression::Deflate => miniz_oxide::inflate::decompress_slice_iter_to_slice(
+                &mut data,
+                std::iter::once(stored.as_slice()),
+                false,
+                true,
+            )
+            .map_err(|_| invalid_data("Invalid CSO block"))?,
+            Compression::Lz4 => lz4_decompress(&stored, &mut data[..block_len])
+                .ok_or_else(|| invalid_data("Invalid ZSO block"))?,
+        };
+        if count < block_len {
+            return Err(invalid_data("Truncated CSO block"));
+        }
+        data.truncate(block_len);
+        Ok(data)
+    }
+}
+
+/// Decompress an LZ4 block until `out` is full. Compressed blocks are padded
+/// to the alignment, so the end of the input can't be relied on.
+fn lz4_decompress(input: &[u8], out: &mut [u8]) -> Option<usize> {
+    let mut input = input.iter().copied();
+    let mut next = || input.next();
+    let mut pos = 0;
+    loop {
+        let token = next()?;
+        let literals = lz4_length(token as usize >> 4, &mut next)?;
+        for _ in 0..literals {
+            *out.get_mut(pos)? = next()?;
+            pos += 1;
+        }
+        if pos == out.len() {
+            return Some(pos);
+        }
+
+        let offset = next()? as usize | (next()? as usize) << 8;
+        let len = lz4_length(token as usize & 0xf, &mut next)? + 4;
+        if offset == 0 || offset > pos || pos + len > out.len() {
+            return None;
+        }
+        // Matches may overlap the bytes they copy
+        for i in pos..pos + len {
+            out[i] = out[i - offset];
+        }
+        pos += len;
+    }
+}
+
+/// A literal or match length, which continues in the following bytes if
+/// the 4 bits of the token are all set
+fn lz4_length(mut len: usize, next: &mut impl FnMut() -> Option<u8>) -> Option<usize> {
+    if len == 15 {
+        loop {
+            let byte = next()?;
+            len += byte as usize;
+            if byte != 255 {
+                break;
+            }
+        }
+    }
+    Some(len)
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl<T: Read + Seek> Read for CsoReader<T> {
+    fn read(&mut self, mut buf: &mut [u8]) -> io::Result<usize> {
+        // Read whole requests across blocks, since `read_at` reads once
+        let start = self.seek;
+        while !buf.is_empty() && self.seek < self.size {
+            let block_size = self.block_size as u64;
+            let pos = (self.seek % block_size) as usize;
+            let data = self.block(self.seek / block_size)?;
+            let count = (data.len() - pos).min(buf.len());
+            buf[..count].copy_from_slice(&data[pos..pos + count]);
+            buf = &mut buf[count..];
+            self.seek += count as u64;
+        }
+        Ok((self.seek - start) as usize)
+    }
+}
+
+impl<T: Read + Seek> Seek for CsoReader<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let seek = match pos {
+            SeekFrom::Start(pos) => pos as i64,
+            SeekFrom::End(pos) => self.size as i64 + pos,
+            SeekFrom::Current(pos) => self.seek as i64 + pos,
+        };
+
+        if seek < 0 {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek"))
+        } else {
+            self.seek = seek as u64;
+            Ok(seek as u64)
+        }
+    }
+}
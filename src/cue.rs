@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use crate::disc::{Track, TrackMode};
+use crate::{ISOError, Result};
+
+// Sectors per second of a CD
+const FRAMES_PER_SECOND: u32 = 75;
+// Sectors between sessions that images don't store: the lead-out, which is
+// longer after the first session, the lead-in, and the pregap of the first
+// track of the next session
+const FIRST_LEAD_OUT: u32 = 6750;
+const LEAD_OUT: u32 = 2250;
+const LEAD_IN: u64 = 4500;
+const SESSION_PREGAP: u64 = 150;
+
+/// A CUE sheet, describing the tracks of a disc stored in one or more BIN
+/// files
+#[derive(Clone, Debug, Default)]
+pub struct CueSheet {
+    pub files: Vec<CueFile>,
+    /// Length in sectors of the lead-out of each session, from `REM
+    /// LEAD-OUT` comments
+    pub lead_outs: Vec<(u8, u32)>,
+}
+
+/// A `FILE` of a CUE sheet, and the tracks stored in it
+#[derive(Clone, Debug)]
+pub struct CueFile {
+    /// Path of the file, relative to the CUE sheet
+    pub name: String,
+    /// Format of the file, like `BINARY`
+    pub file_type: String,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CueTrack {
+    pub number: u8,
+    /// Session of the track, from `REM SESSION` comments; 1 if there are
+    /// none
+    pub session: u8,
+    pub mode: TrackMode,
+    /// Sectors of silence before the track that are not stored in the file
+    pub pregap: u32,
+    /// Sectors of silence after the track that are not stored in the file
+    pub postgap: u32,
+    /// Index numbers, and their positions in sectors from the start of the
+    /// file
+    pub indexes: Vec<(u8, u32)>,
+}
+
+impl CueSheet {
+    /// Parse the text of a CUE sheet. Commands that don't affect the
+    /// layout of the disc, like `TITLE` or `FLAGS`, are ignored.
+    pub fn parse(text: &str) -> Result<CueSheet> {
+        let mut sheet = CueSheet::default();
+        let mut session = 1;
+        for line in text.trim_start_matches('\u{feff}').lines() {
+            let line = line.trim();
+            let (command, args) = match line.find(char::is_whitespace) {
+                Some(i) => (&line[..i], line[i..].trim_start()),
+                None => (line, ""),
+            };
+
+            match command.to_ascii_uppercase().as_str() {
+                "FILE" => {
+                    let (name, file_type) = file_args(args)?;
+                    sheet.files.push(CueFile {
+                        name,
+                        file_type,
+                        tracks: Vec::new(),
+                    });
+                }
+                "TRACK" => {
+                    let mut args = args.split_whitespace();
+                    let (number, mode) = match (args.next(), args.next()) {
+                        (Some(number), Some(mode)) => (number.parse()?, TrackMode::parse(mode)?),
+                        _ => return Err(ISOError::InvalidFs("Invalid CUE TRACK command")),
+                    };
+                    let file = sheet
+                        .files
+                        .last_mut()
+                        .ok_or(ISOError::InvalidFs("CUE TRACK before FILE"))?;
+                    file.tracks.push(CueTrack {
+                        number,
+                        session,
+                        mode,
+                        pregap: 0,
+                        postgap: 0,
+                        indexes: Vec::new(),
+                    });
+                }
+                "INDEX" => {
+                    let mut args = args.split_whitespace();
+                    let index = match (args.next(), args.next()) {
+                        (Some(number), Some(time)) => (number.parse()?, msf(time)?),
+                        _ => return Err(ISOError::InvalidFs("Invalid CUE INDEX command")),
+                    };
+                    sheet.current_track()?.indexes.push(index);
+                }
+                "PREGAP" => sheet.current_track()?.pregap = msf(args)?,
+                "POSTGAP" => sheet.current_track()?.postgap = msf(args)?,
+                // Written by tools that dump multi-session discs
+                "REM" => {
+                    let mut args = args.split_whitespace();
+                    match (args.next(), args.next()) {
+                        (Some("SESSION"), Some(number)) => session = number.parse()?,
+                        (Some("LEAD-OUT"), Some(time)) => {
+                            sheet.lead_outs.push((session, msf(time)?))
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(sheet)
+    }
+
+    fn current_track(&mut self) -> Result<&mut CueTrack> {
+        self.files
+            .last_mut()
+            .and_then(|x| x.tracks.last_mut())
+            .ok_or(ISOError::InvalidFs("CUE command outside of a TRACK"))
+    }
+
+    /// Length in sectors of the lead-out of `session`
+    fn lead_out(&self, session: u8) -> u64 {
+        let default = if session == 1 {
+            FIRST_LEAD_OUT
+        } else {
+            LEAD_OUT
+        };
+        self.lead_outs
+            .iter()
+            .find(|x| x.0 == session)
+            .map_or(default, |x| x.1) as u64
+    }
+
+    /// Lay out the tracks on the disc, given the size in bytes of each
+    /// file. Files follow each other, separated only by the gaps that are
+    /// not stored in them, and by the lead-out and lead-in between
+    /// sessions.
+    pub fn tracks(&self, file_sizes: &[u64]) -> Result<Vec<Track>> {
+        if file_sizes.len() != self.files.len() {
+            return Err(ISOError::InvalidFs("Wrong number of CUE files"));
+        }
+
+        let mut tracks = Vec::new();
+        let mut file_start = 0;
+        let mut session = None;
+        for (file_index, (file, &file_size)) in self.files.iter().zip(file_sizes).enumerate() {
+            // Gaps that are not stored in the file move the tracks after them
+            let mut gaps = 0;
+            // First sector of the track, and its byte offset in the file
+            let mut boundary = (0, 0);
+            for (i, track) in file.tracks.iter().enumerate() {
+                let sector_size = track.mode.sector_size() as u64;
+                let index1 = track
+                    .indexes
+                    .iter()
+                    .find(|x| x.0 == 1)
+                    .ok_or(ISOError::InvalidFs("CUE TRACK without INDEX 01"))?
+                    .1 as u64;
+                let first = track.indexes.iter().map(|x| x.1 as u64).min().unwrap();
+                if first < boundary.0 {
+                    return Err(ISOError::InvalidFs("CUE tracks out of order"));
+                }
+                if i == 0 {
+                    boundary = (first, first * sector_size);
+                }
+                let offset = boundary.1 + (index1 - boundary.0) * sector_size;
+
+                // The track ends where the next one begins, or at the end of
+                // the file
+                let next = file
+                    .tracks
+                    .get(i + 1)
+                    .and_then(|x| x.indexes.iter().map(|x| x.1).min());
+                let length = match next {
+                    Some(next) => (next as u64).saturating_sub(index1),
+                    None => file_size.saturating_sub(offset) / sector_size,
+                };
+
+                gaps += track.pregap as u64;
+                if let Some(previous) = session.filter(|&x| x != track.session) {
+                    gaps += self.lead_out(previous) + LEAD_IN;
+                    // Unless the sheet gives the pregap of the first track
+                    // of the session, it has the standard one
+                    if track.pregap == 0 && index1 == first {
+                        gaps += SESSION_PREGAP;
+                    }
+                }
+                session = Some(track.session);
+                tracks.push(Track {
+                    number: track.number,
+                    session: track.session,
+                    mode: track.mode,
+                    file: file_index,
+                    offset,
+                    start: file_start + gaps + index1,
+                    pregap: track.pregap as u64 + index1 - first,
+                    stored_pregap: index1 - first,
+                    length,
+                });
+                gaps += track.postgap as u64;
+
+                let end = index1 + length;
+                boundary = (end, offset + length * sector_size);
+            }
+
+            let sectors = match file.tracks.last() {
+                Some(track) => {
+                    let sector_size = track.mode.sector_size() as u64;
+                    boundary.0 + file_size.saturating_sub(boundary.1) / sector_size
+                }
+                None => 0,
+            };
+            file_start += gaps + sectors;
+        }
+
+        // Disc addresses start at the INDEX 01 of the first track, so its
+        // pregap, stored or not, comes before sector 0
+        let origin = tracks.first().map_or(0, |x| x.start);
+        for track in &mut tracks {
+            track.start -= origin;
+        }
+
+        Ok(tracks)
+    }
+}
+
+/// Split the arguments of a `FILE` command into its name, which may be
+/// quoted, and its type
+fn file_args(args: &str) -> Result<(String, String)> {
+    let (name, rest) = if let Some(quoted) = args.strip_prefix('"') {
+        let end = quoted
+            .find('"')
+            .ok_or(ISOError::InvalidFs("Unterminated CUE FILE name"))?;
+        (&quoted[..end], &quoted[end + 1..])
+    } else {
+        // The type comes last, so unquoted names may contain spaces
+        match args.rfind(char::is_whitespace) {
+            Some(end) => (&args[..end], &args[end..]),
+            None => (args, ""),
+        }
+    };
+    Ok((name.trim().to_string(), rest.trim().to_ascii_uppercase()))
+}
+
+/// Parse a `mm:ss:ff` time into a number of sectors
+fn msf(time: &str) -> Result<u32> {
+    let mut parts = time.trim().split(':');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(minutes), Some(seconds), Some(frames), None) => {
+            let minutes: u32 = minutes.parse()?;
+            let seconds: u32 = seconds.parse()?;
+            let frames: u32 = frames.parse()?;
+            if seconds >= 60 || frames >= FRAMES_PER_SECOND {
+                return Err(ISOError::InvalidFs("Invalid CUE time"));
+            }
+            minutes
+                .checked_mul(60 * FRAMES_PER_SECOND)
+                .and_then(|x| x.checked_add(seconds * FRAMES_PER_SECOND + frames))
+                .ok_or(ISOError::InvalidFs("Invalid CUE time"))
+        }
+        _ => Err(ISOError::InvalidFs("Invalid CUE time")),
+    }
+}
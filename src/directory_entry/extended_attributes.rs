@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use super::Timestamps;
+use crate::parse::{extended_attribute_record, DirectoryEntryHeader};
+use crate::{FileRef, ISO9660Reader, ISOError, Result};
+
+/// Extended attribute record (XAR), recorded in the logical blocks before
+/// the data of a file or directory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtendedAttributes {
+    pub owner: u16,
+    pub group: u16,
+    /// Permission bits, where a set bit denies access. See `mode` for
+    /// POSIX permission bits.
+    pub permissions: u16,
+    /// Creation, modification, expiration and effective times, if specified
+    pub timestamps: Timestamps,
+    pub record_format: u8,
+    pub record_attributes: u8,
+    pub record_length: u16,
+    /// Identifier of the system that can recognize `system_use`
+    pub system_identifier: String,
+    pub system_use: Vec<u8>,
+    /// Version of the record; 1 in ISO 9660
+    pub version: u8,
+    pub application_use: Vec<u8>,
+    pub escape_sequences: Vec<u8>,
+}
+
+impl ExtendedAttributes {
+    /// Read and execute permissions for owner, group and others, as in
+    /// `st_mode`
+    pub fn mode(&self) -> u32 {
+        [
+            (1 << 4, 0o400),
+            (1 << 6, 0o100),
+            (1 << 8, 0o040),
+            (1 << 10, 0o010),
+            (1 << 12, 0o004),
+            (1 << 14, 0o001),
+        ]
+        .iter()
+        .filter(|(bit, _)| self.permissions & bit == 0)
+        .map(|(_, mode)| mode)
+        .sum()
+    }
+}
+
+/// Read the extended attribute record of the extent described by `header`,
+/// if it has one
+pub(crate) fn read_extended_attributes<T: ISO9660Reader>(
+    file: &FileRef<T>,
+    header: &DirectoryEntryHeader,
+) -> Result<Option<ExtendedAttributes>> {
+    if header.extended_attribute_record_length == 0 {
+        return Ok(None);
+    }
+
+    let length = header.extended_attribute_record_length as usize * file.block_size() as usize;
+    let mut buf = vec![0; length];
+    let count = file.read_at(&mut buf, header.extent_loc as u64)?;
+    if count != length {
+        return Err(ISOError::ReadSize(length, count));
+    }
+
+    Ok(Some(extended_attribute_record(&buf)?.1))
+}
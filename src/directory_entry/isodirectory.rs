@@ -1,15 +1,19 @@
 // SPDX-License-Identifier: (MIT OR Apache-2.0)
 
+use std::cmp::min;
 use std::{fmt, str};
 
 use time::OffsetDateTime;
 
-use crate::parse::{DirectoryEntryHeader, FileFlags};
+use super::metadata::S_IFDIR;
+use super::{read_extended_attributes, ExtendedAttributes, Metadata, RockRidge, Timestamps};
+use crate::parse::{DirectoryEntryHeader, DirectoryRecord, FileFlags};
 use crate::{DirectoryEntry, FileRef, ISO9660Reader, ISOError, Result};
 
 pub struct ISODirectory<T: ISO9660Reader> {
     pub(crate) header: DirectoryEntryHeader,
     pub identifier: String,
+    pub(crate) rock_ridge: Option<RockRidge>,
     file: FileRef<T>,
 }
 
@@ -18,6 +22,7 @@ impl<T: ISO9660Reader> Clone for ISODirectory<T> {
         ISODirectory {
             header: self.header.clone(),
             identifier: self.identifier.clone(),
+            rock_ridge: self.rock_ridge.clone(),
             file: self.file.clone(),
         }
     }
@@ -36,24 +41,35 @@ impl<T: ISO9660Reader> ISODirectory<T> {
     pub(crate) fn new(
         header: DirectoryEntryHeader,
         mut identifier: String,
+        rock_ridge: Option<RockRidge>,
         file: FileRef<T>,
     ) -> ISODirectory<T> {
         if &identifier == "\u{0}" {
             identifier = ".".to_string();
         } else if &identifier == "\u{1}" {
             identifier = "..".to_string();
+        } else if let Some(name) = rock_ridge.as_ref().and_then(|x| x.name.as_ref()) {
+            identifier = name.clone();
         }
 
         ISODirectory {
             header,
             identifier,
+            rock_ridge,
             file,
         }
     }
 
+    /// Number of logical blocks of the directory, in the logical block size
+    /// of the volume
     pub fn block_count(&self) -> u32 {
         let len = self.header.extent_length;
-        (len + 2048 - 1) / 2048 // ceil(len / 2048)
+        len.div_ceil(self.file.block_size() as u32)
+    }
+
+    /// Number of 2048 byte sectors of the directory, which it is read in
+    fn sector_count(&self) -> u64 {
+        (self.header.extent_length as u64).div_ceil(2048)
     }
 
     pub fn read_entry_at(
@@ -62,42 +78,116 @@ impl<T: ISO9660Reader> ISODirectory<T> {
         buf_block_num: &mut Option<u64>,
         offset: u64,
     ) -> Result<(DirectoryEntry<T>, Option<u64>)> {
+        let ((header, identifier, system_use), mut next_offset) =
+            self.read_record_at(block, buf_block_num, offset)?;
+        let mut entry = DirectoryEntry::new(header, identifier, system_use, self.file.clone())?;
+
+        // A file split over several extents has a directory record for
+        // each, all but the last with the multi-extent flag set.
+        let mut multi_extent = entry.header().file_flags.contains(FileFlags::MULTIEXTENT);
+        while multi_extent {
+            let offset =
+                next_offset.ok_or(ISOError::InvalidFs("Unterminated multi-extent file"))?;
+            let ((header, _, _), next) = self.read_record_at(block, buf_block_num, offset)?;
+            if let DirectoryEntry::File(ref mut file) = entry {
+                file.push_extent(&header);
+            }
+            multi_extent = header.file_flags.contains(FileFlags::MULTIEXTENT);
+            next_offset = next;
+        }
+
+        // Hide the directory that Rock Ridge relocation moves deep
+        // directories into; mkisofs calls it "rr_moved" or ".rr_moved".
+        // A directory of that name that holds no relocated directories is
+        // an ordinary one.
+        if let DirectoryEntry::Directory(ref mut dir) = entry {
+            let name = dir.identifier.trim_start_matches('.');
+            if dir.rock_ridge.is_some()
+                && self.header.extent_loc == self.file.root_loc()
+                && name.eq_ignore_ascii_case("rr_moved")
+                && dir.holds_relocated()?
+            {
+                if let Some(rock_ridge) = dir.rock_ridge.as_mut() {
+                    rock_ridge.relocated = true;
+                }
+            }
+        }
+
+        Ok((entry, next_offset))
+    }
+
+    /// Whether the directory holds directories moved into it by Rock Ridge
+    /// relocation, which have RE entries
+    fn holds_relocated(&self) -> Result<bool> {
+        let mut block = [0; 2048];
+        let mut block_num = None;
+        let mut offset = Some(0);
+        while let Some(current) = offset {
+            let (entry, next) = self.read_entry_at(&mut block, &mut block_num, current)?;
+            if entry.is_relocated() {
+                return Ok(true);
+            }
+            offset = next;
+        }
+        Ok(false)
+    }
+
+    /// Parse the directory record at `offset`, returning it with the offset
+    /// of the next one
+    fn read_record_at<'a>(
+        &self,
+        block: &'a mut [u8; 2048],
+        buf_block_num: &mut Option<u64>,
+        offset: u64,
+    ) -> Result<(DirectoryRecord<'a>, Option<u64>)> {
         let mut block_num = offset / 2048;
         let mut block_pos = (offset % 2048) as usize;
 
+        // Directory records never cross a 2048 byte logical sector, so the
+        // directory is read a sector at a time, whatever the block size.
         if buf_block_num != &Some(block_num) {
-            let lba = self.header.extent_loc as u64 + block_num;
-            let count = self.file.read_at(block, lba)?;
+            // The records follow the extended attribute record, if any
+            let lba = self.header.extent_loc as u64
+                + self.header.extended_attribute_record_length as u64
+                + block_num * self.file.blocks_per_sector();
+            let len = min(2048, self.header.extent_length as u64 - block_num * 2048) as usize;
+            let len = len.next_multiple_of(self.file.block_size() as usize);
+            block.fill(0);
+            let count = self.file.read_at(&mut block[..len], lba)?;
 
-            if count != 2048 {
+            if count != len {
                 *buf_block_num = None;
-                return Err(ISOError::ReadSize(2048, count));
+                return Err(ISOError::ReadSize(len, count));
             }
 
             *buf_block_num = Some(block_num);
         }
 
-        let (header, identifier) = DirectoryEntryHeader::parse(&block[block_pos..])?;
-        block_pos += header.length as usize;
+        let block: &'a [u8; 2048] = block;
+        let record = DirectoryEntryHeader::parse(&block[block_pos..])?;
+        block_pos += record.0.length as usize;
 
-        let entry = DirectoryEntry::new(header, identifier, self.file.clone())?;
-
-        // All bytes after the last directory entry are zero.
-        if block_pos >= (2048 - 33) || block[block_pos] == 0 {
+        // All bytes after the last directory entry of a logical block are
+        // zero.
+        let block_size = self.file.block_size() as usize;
+        while block_pos < 2048 - 33 && block[block_pos] == 0 {
+            block_pos = (block_pos / block_size + 1) * block_size;
+        }
+        if block_pos >= (2048 - 33) {
             block_num += 1;
             block_pos = 0;
         }
 
-        let next_offset = if block_num < self.block_count() as u64 {
+        let next_offset = if block_num < self.sector_count() {
             Some(2048 * block_num + block_pos as u64)
         } else {
             None
         };
 
-        Ok((entry, next_offset))
+        Ok((record, next_offset))
     }
 
-    pub fn contents(&self) -> ISODirectoryIterator<T> {
+    pub fn contents(&self) -> ISODirectoryIterator<'_, T> {
         ISODirectoryIterator {
             directory: self,
             block: [0; 2048],
@@ -110,6 +200,57 @@ impl<T: ISO9660Reader> ISODirectory<T> {
         self.header.time
     }
 
+    /// Timestamps from the Rock Ridge TF entry, if any
+    pub fn timestamps(&self) -> Timestamps {
+        self.rock_ridge
+            .as_ref()
+            .map(|x| x.timestamps)
+            .unwrap_or_default()
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        self.rock_ridge
+            .as_ref()
+            .and_then(|x| x.metadata)
+            .unwrap_or_else(|| Metadata::synthesize(S_IFDIR))
+    }
+
+    /// The extended attribute record of the directory, if it has one
+    pub fn extended_attributes(&self) -> Result<Option<ExtendedAttributes>> {
+        read_extended_attributes(&self.file, &self.header)
+    }
+
+    /// Call `visit` with the path and entry of everything below the
+    /// directory, depth first, with paths like `DIR/FILE.TXT`
+    pub(crate) fn walk(
+        &self,
+        visit: &mut dyn FnMut(&str, &DirectoryEntry<T>) -> Result<()>,
+    ) -> Result<()> {
+        self.walk_prefix("", visit)
+    }
+
+    fn walk_prefix(
+        &self,
+        prefix: &str,
+        visit: &mut dyn FnMut(&str, &DirectoryEntry<T>) -> Result<()>,
+    ) -> Result<()> {
+        for entry in self.contents() {
+            let entry = entry?;
+            let name = entry.identifier();
+            if name == "." || name == ".." {
+                continue;
+            }
+
+            let path = format!("{}{}", prefix, name);
+            visit(&path, &entry)?;
+            if let DirectoryEntry::Directory(dir) = &entry {
+                dir.walk_prefix(&format!("{}/", path), visit)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn find(&self, identifier: &str) -> Result<Option<DirectoryEntry<T>>> {
         for entry in self.contents() {
             let entry = entry?;
@@ -120,7 +261,13 @@ impl<T: ISO9660Reader> ISODirectory<T> {
             {
                 continue;
             }
-            if entry.identifier().eq_ignore_ascii_case(identifier) {
+            // Rock Ridge names are case sensitive, unlike ISO 9660 ones
+            let matches = if entry.rock_ridge().and_then(|x| x.name.as_ref()).is_some() {
+                entry.identifier() == identifier
+            } else {
+                entry.identifier().eq_ignore_ascii_case(identifier)
+            };
+            if matches {
                 return Ok(Some(entry));
             }
         }
@@ -140,16 +287,29 @@ impl<'a, T: ISO9660Reader> Iterator for ISODirectoryIterator<'a, T> {
     type Item = Result<DirectoryEntry<T>>;
 
     fn next(&mut self) -> Option<Result<DirectoryEntry<T>>> {
-        let offset = self.next_offset?;
-        match self
-            .directory
-            .read_entry_at(&mut self.block, &mut self.block_num, offset)
-        {
-            Ok((entry, next_offset)) => {
-                self.next_offset = next_offset;
-                Some(Ok(entry))
+        loop {
+            let offset = self.next_offset?;
+            match self
+                .directory
+                .read_entry_at(&mut self.block, &mut self.block_num, offset)
+            {
+                Ok((entry, next_offset)) => {
+                    self.next_offset = next_offset;
+                    if !entry.is_relocated() {
+                        return Some(Ok(entry));
+                    }
+                }
+                Err(err) => {
+                    // Move past the record, so callers that carry on after
+                    // an error don't get the same one again
+                    self.next_offset = self
+                        .directory
+                        .read_record_at(&mut self.block, &mut self.block_num, offset)
+                        .ok()
+                        .and_then(|x| x.1);
+                    return Some(Err(err));
+                }
             }
-            Err(err) => Some(Err(err)),
         }
     }
 }
@@ -7,8 +7,14 @@ use std::str::FromStr;
 
 use time::OffsetDateTime;
 
-use super::DirectoryEntryHeader;
-use crate::{FileRef, ISO9660Reader, Result};
+use super::metadata::S_IFREG;
+use super::{
+    read_extended_attributes, DirectoryEntryHeader, ExtendedAttributes, Metadata, RockRidge,
+    Timestamps,
+};
+use crate::parse::{XaAttributes, XaRecord};
+use crate::raw_sector::FORM2_DATA_SIZE;
+use crate::{FileRef, Hierarchy, ISO9660Reader, Result};
 
 #[derive(Clone)]
 pub struct ISOFile<T: ISO9660Reader> {
@@ -16,6 +22,10 @@ pub struct ISOFile<T: ISO9660Reader> {
     pub identifier: String,
     // File version; ranges from 1 to 32767
     pub version: u16,
+    pub(crate) rock_ridge: Option<RockRidge>,
+    /// Location and length of each extent; files over 4 GiB are split
+    /// into several directory records
+    extents: Vec<(u32, u32)>,
     file: FileRef<T>,
 }
 
@@ -33,80 +43,203 @@ impl<T: ISO9660Reader> ISOFile<T> {
     pub(crate) fn new(
         header: DirectoryEntryHeader,
         mut identifier: String,
+        rock_ridge: Option<RockRidge>,
         file: FileRef<T>,
     ) -> Result<ISOFile<T>> {
         // Files (not directories) in ISO 9660 have a version number, which is
         // provided at the end of the identifier, seperated by ';'.
         // If not, assume 1.
+        // ISO 9660:1999 identifiers have no version, and may contain ';'.
         let version = match identifier.rfind(';') {
-            Some(idx) => {
+            Some(idx) if file.hierarchy() != Hierarchy::Enhanced => {
                 let version = u16::from_str(&identifier[idx + 1..])?;
                 identifier.truncate(idx);
                 version
             }
-            None => 1,
+            _ => 1,
         };
 
         // Files without an extension have a '.' at the end
-        if identifier.ends_with('.') {
+        if identifier.ends_with('.') && file.hierarchy() != Hierarchy::Enhanced {
             identifier.pop();
         }
 
+        // Rock Ridge names are used as-is, without a version
+        if let Some(name) = rock_ridge.as_ref().and_then(|x| x.name.as_ref()) {
+            identifier = name.clone();
+        }
+
         Ok(ISOFile {
+            extents: vec![data_extent(&header)],
             header,
             identifier,
             version,
+            rock_ridge,
             file,
         })
     }
 
-    pub fn size(&self) -> u32 {
-        self.header.extent_length
+    /// Size of the file, which may be split over several extents. Mode 2
+    /// Form 2 files read from raw sectors hold 2324 bytes per sector.
+    pub fn size(&self) -> u64 {
+        let sector_size = self.sector_size();
+        self.extents
+            .iter()
+            .map(|x| extent_size(x.1, sector_size))
+            .sum()
+    }
+
+    /// The CD-ROM XA record of the file, if the disc has them
+    pub fn xa(&self) -> Option<&XaRecord> {
+        self.header.xa.as_ref()
+    }
+
+    /// Size of the data of each sector of the file when read: 2324 bytes
+    /// for Mode 2 Form 2 files, if the reader has raw sectors
+    fn sector_size(&self) -> u64 {
+        let form2 = self
+            .header
+            .xa
+            .as_ref()
+            .is_some_and(|x| x.attributes.contains(XaAttributes::MODE2_FORM2));
+        if form2 && self.file.reader().has_raw_sectors() {
+            FORM2_DATA_SIZE as u64
+        } else {
+            2048
+        }
     }
 
     pub fn time(&self) -> OffsetDateTime {
         self.header.time
     }
 
+    /// Timestamps from the Rock Ridge TF entry, if any
+    pub fn timestamps(&self) -> Timestamps {
+        self.rock_ridge
+            .as_ref()
+            .map(|x| x.timestamps)
+            .unwrap_or_default()
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        self.rock_ridge
+            .as_ref()
+            .and_then(|x| x.metadata)
+            .unwrap_or_else(|| Metadata::synthesize(S_IFREG))
+    }
+
     pub fn read(&self) -> ISOFileReader<T> {
-        ISOFileReader {
-            buf: [0; 2048],
-            buf_lba: None,
-            seek: 0,
-            start_lba: self.header.extent_loc,
-            size: self.size() as usize,
-            file: self.file.clone(),
-        }
+        let sector_size = self.sector_size();
+        let extents = self
+            .extents
+            .iter()
+            .map(|&(loc, length)| (loc as u64, extent_size(length, sector_size)))
+            .collect();
+        let mut reader = ISOFileReader::new(self.file.clone(), extents);
+        reader.sector_size = sector_size;
+        reader
+    }
+
+    /// Location and length of the data of each extent
+    pub(crate) fn extents(&self) -> &[(u32, u32)] {
+        &self.extents
+    }
+
+    /// Add the extent of the next directory record of a multi-extent file
+    pub(crate) fn push_extent(&mut self, header: &DirectoryEntryHeader) {
+        self.extents.push(data_extent(header));
+    }
+
+    /// The extended attribute record of the file, if it has one
+    pub fn extended_attributes(&self) -> Result<Option<ExtendedAttributes>> {
+        read_extended_attributes(&self.file, &self.header)
+    }
+}
+
+/// Size of the data of an extent whose recorded length is `length`, when
+/// each sector holds `sector_size` bytes
+fn extent_size(length: u32, sector_size: u64) -> u64 {
+    if sector_size == 2048 {
+        length as u64
+    } else {
+        // The recorded length counts 2048 bytes per sector
+        (length as u64).div_ceil(2048) * sector_size
     }
 }
 
+/// Location and length of the data of an extent, which follows its extended
+/// attribute record
+fn data_extent(header: &DirectoryEntryHeader) -> (u32, u32) {
+    let loc = header.extent_loc + header.extended_attribute_record_length as u32;
+    (loc, header.extent_length)
+}
+
 pub struct ISOFileReader<T: ISO9660Reader> {
-    buf: [u8; 2048],
+    buf: [u8; FORM2_DATA_SIZE],
+    /// Bytes of data per sector; 2048, or 2324 for Mode 2 Form 2 files
+    sector_size: u64,
     buf_lba: Option<u64>,
-    seek: usize,
-    start_lba: u32,
-    size: usize,
+    seek: u64,
+    /// Location and length of each extent of the file
+    extents: Vec<(u64, u64)>,
+    size: u64,
     file: FileRef<T>,
 }
 
+impl<T: ISO9660Reader> ISOFileReader<T> {
+    pub(crate) fn new(file: FileRef<T>, extents: Vec<(u64, u64)>) -> ISOFileReader<T> {
+        ISOFileReader {
+            buf: [0; FORM2_DATA_SIZE],
+            sector_size: 2048,
+            buf_lba: None,
+            seek: 0,
+            size: extents.iter().map(|x| x.1).sum(),
+            extents,
+            file,
+        }
+    }
+
+    /// The extent containing the byte at `seek`, and the offset of that
+    /// byte in the extent
+    fn extent_at(&self, mut seek: u64) -> (u64, u64, u64) {
+        for &(lba, length) in &self.extents {
+            if seek < length {
+                return (lba, length, seek);
+            }
+            seek -= length;
+        }
+        unreachable!()
+    }
+}
+
 impl<T: ISO9660Reader> Read for ISOFileReader<T> {
     fn read(&mut self, mut buf: &mut [u8]) -> io::Result<usize> {
         let mut seek = self.seek;
         while !buf.is_empty() && seek < self.size {
-            let lba = self.start_lba as u64 + (seek as u64 / 2048);
+            let (start_lba, length, pos) = self.extent_at(seek);
+
+            // Read a sector at a time, whatever the block size
+            let sector_size = self.sector_size;
+            let lba = start_lba + (pos / sector_size) * self.file.blocks_per_sector();
             if self.buf_lba != Some(lba) {
-                self.file.read_at(&mut self.buf, lba)?;
+                let buf = &mut self.buf[..sector_size as usize];
+                if sector_size == 2048 {
+                    self.file.read_at(buf, lba)?;
+                } else {
+                    let sector = lba * self.file.block_size() as u64 / 2048;
+                    self.file.reader().read_form2_at(buf, sector)?;
+                }
                 self.buf_lba = Some(lba);
             }
 
-            let start = seek % 2048;
-            let end = min(self.size - (seek / 2048) * 2048, 2048);
-            seek += buf.write(&self.buf[start..end]).unwrap();
+            let start = (pos % sector_size) as usize;
+            let end = min(length - (pos / sector_size) * sector_size, sector_size) as usize;
+            seek += buf.write(&self.buf[start..end]).unwrap() as u64;
         }
 
         let bytes = seek - self.seek;
         self.seek = seek;
-        Ok(bytes)
+        Ok(bytes as usize)
     }
 
     // TODO implement `read_buf` on nightly
@@ -123,7 +256,7 @@ impl<T: ISO9660Reader> Seek for ISOFileReader<T> {
         if seek < 0 {
             Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek"))
         } else {
-            self.seek = seek as usize;
+            self.seek = seek as u64;
             Ok(seek as u64)
         }
     }
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use time::OffsetDateTime;
+
+use super::metadata::{S_IFBLK, S_IFCHR, S_IFIFO, S_IFMT, S_IFSOCK};
+use super::{DirectoryEntryHeader, Metadata, RockRidge, Timestamps};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecialFileType {
+    CharacterDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+}
+
+impl SpecialFileType {
+    /// Decode the file type bits of a POSIX mode
+    pub(crate) fn from_mode(mode: u32) -> Option<SpecialFileType> {
+        match mode & S_IFMT {
+            S_IFCHR => Some(SpecialFileType::CharacterDevice),
+            S_IFBLK => Some(SpecialFileType::BlockDevice),
+            S_IFIFO => Some(SpecialFileType::Fifo),
+            S_IFSOCK => Some(SpecialFileType::Socket),
+            _ => None,
+        }
+    }
+}
+
+/// A Rock Ridge device node, FIFO or socket
+#[derive(Clone, Debug)]
+pub struct ISOSpecialFile {
+    pub(crate) header: DirectoryEntryHeader,
+    pub identifier: String,
+    pub(crate) rock_ridge: RockRidge,
+    /// From the PX entry, whose mode makes the entry a special file
+    metadata: Metadata,
+    file_type: SpecialFileType,
+}
+
+impl ISOSpecialFile {
+    pub(crate) fn new(
+        header: DirectoryEntryHeader,
+        mut identifier: String,
+        rock_ridge: RockRidge,
+        metadata: Metadata,
+        file_type: SpecialFileType,
+    ) -> ISOSpecialFile {
+        if let Some(name) = &rock_ridge.name {
+            identifier = name.clone();
+        }
+
+        ISOSpecialFile {
+            header,
+            identifier,
+            rock_ridge,
+            metadata,
+            file_type,
+        }
+    }
+
+    pub fn file_type(&self) -> SpecialFileType {
+        self.file_type
+    }
+
+    /// Major and minor number of a device, from the Rock Ridge PN entry
+    pub fn device(&self) -> Option<(u32, u32)> {
+        self.rock_ridge.device
+    }
+
+    pub fn time(&self) -> OffsetDateTime {
+        self.header.time
+    }
+
+    /// Timestamps from the Rock Ridge TF entry
+    pub fn timestamps(&self) -> Timestamps {
+        self.rock_ridge.timestamps
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        self.metadata
+    }
+}
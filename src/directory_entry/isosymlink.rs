@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use time::OffsetDateTime;
+
+use super::metadata::S_IFLNK;
+use super::{DirectoryEntryHeader, Metadata, RockRidge, Timestamps};
+
+/// A Rock Ridge symbolic link
+#[derive(Clone, Debug)]
+pub struct ISOSymlink {
+    pub(crate) header: DirectoryEntryHeader,
+    pub identifier: String,
+    pub(crate) rock_ridge: RockRidge,
+}
+
+impl ISOSymlink {
+    pub(crate) fn new(
+        header: DirectoryEntryHeader,
+        mut identifier: String,
+        rock_ridge: RockRidge,
+    ) -> ISOSymlink {
+        if let Some(name) = &rock_ridge.name {
+            identifier = name.clone();
+        }
+
+        ISOSymlink {
+            header,
+            identifier,
+            rock_ridge,
+        }
+    }
+
+    /// The path the link points to, which may be relative to the directory
+    /// containing the link.
+    pub fn target(&self) -> &str {
+        self.rock_ridge.symlink.as_deref().unwrap_or_default()
+    }
+
+    pub fn time(&self) -> OffsetDateTime {
+        self.header.time
+    }
+
+    /// Timestamps from the Rock Ridge TF entry
+    pub fn timestamps(&self) -> Timestamps {
+        self.rock_ridge.timestamps
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        self.rock_ridge
+            .metadata
+            .unwrap_or_else(|| Metadata::synthesize(S_IFLNK))
+    }
+}
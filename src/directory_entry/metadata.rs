@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use time::OffsetDateTime;
+
+// File type bits of `Metadata::mode`, as in POSIX `st_mode`
+pub(crate) const S_IFMT: u32 = 0o170000;
+pub(crate) const S_IFSOCK: u32 = 0o140000;
+pub(crate) const S_IFLNK: u32 = 0o120000;
+pub(crate) const S_IFREG: u32 = 0o100000;
+pub(crate) const S_IFBLK: u32 = 0o060000;
+pub(crate) const S_IFDIR: u32 = 0o040000;
+pub(crate) const S_IFCHR: u32 = 0o020000;
+pub(crate) const S_IFIFO: u32 = 0o010000;
+
+/// POSIX attributes of an entry. These come from the Rock Ridge PX entry
+/// when present; otherwise they are synthesized, giving read-only access to
+/// everyone, owned by root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    /// File type and permission bits, as in `st_mode`
+    pub mode: u32,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// File serial number (inode number), only recorded since RRIP 1.12
+    pub serial: Option<u32>,
+}
+
+impl Metadata {
+    pub(crate) fn synthesize(file_type: u32) -> Metadata {
+        let (permissions, nlink) = match file_type {
+            S_IFDIR => (0o555, 2),
+            S_IFLNK => (0o777, 1),
+            _ => (0o444, 1),
+        };
+
+        Metadata {
+            mode: file_type | permissions,
+            nlink,
+            uid: 0,
+            gid: 0,
+            serial: None,
+        }
+    }
+
+    /// Permission bits of `mode`, including setuid, setgid and sticky bits
+    pub fn permissions(&self) -> u32 {
+        self.mode & !S_IFMT
+    }
+}
+
+/// Timestamps recorded in a Rock Ridge TF entry. Each is `None` if not
+/// recorded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timestamps {
+    pub creation: Option<OffsetDateTime>,
+    pub modify: Option<OffsetDateTime>,
+    pub access: Option<OffsetDateTime>,
+    /// Last attribute change, like `st_ctime`
+    pub attributes: Option<OffsetDateTime>,
+    pub backup: Option<OffsetDateTime>,
+    pub expiration: Option<OffsetDateTime>,
+    pub effective: Option<OffsetDateTime>,
+}
@@ -1,34 +1,83 @@
 // SPDX-License-Identifier: (MIT OR Apache-2.0)
 
+pub(crate) use self::extended_attributes::read_extended_attributes;
+pub use self::extended_attributes::ExtendedAttributes;
 pub use self::isodirectory::{ISODirectory, ISODirectoryIterator};
 pub use self::isofile::{ISOFile, ISOFileReader};
+pub use self::isospecialfile::{ISOSpecialFile, SpecialFileType};
+pub use self::isosymlink::ISOSymlink;
+pub use self::metadata::{Metadata, Timestamps};
+pub(crate) use self::rock_ridge::RockRidge;
 
-use crate::parse::{DirectoryEntryHeader, FileFlags};
-use crate::{FileRef, ISO9660Reader, Result};
+use std::str;
 
+use crate::parse::{decode_ucs2, DirectoryEntryHeader, FileFlags};
+use crate::{FileRef, Hierarchy, ISO9660Reader, ISOError, Result};
+
+mod extended_attributes;
 mod isodirectory;
 mod isofile;
+mod isospecialfile;
+mod isosymlink;
+mod metadata;
+mod rock_ridge;
 
 #[derive(Clone, Debug)]
 pub enum DirectoryEntry<T: ISO9660Reader> {
     Directory(ISODirectory<T>),
     File(ISOFile<T>),
+    Symlink(ISOSymlink),
+    Special(ISOSpecialFile),
 }
 
 impl<T: ISO9660Reader> DirectoryEntry<T> {
     pub(crate) fn new(
-        header: DirectoryEntryHeader,
-        identifier: String,
+        mut header: DirectoryEntryHeader,
+        identifier: &[u8],
+        system_use: &[u8],
         file: FileRef<T>,
     ) -> Result<Self> {
-        if header.file_flags.contains(FileFlags::DIRECTORY) {
-            Ok(DirectoryEntry::Directory(ISODirectory::new(
-                header, identifier, file,
-            )))
-        } else {
-            Ok(DirectoryEntry::File(ISOFile::new(
-                header, identifier, file,
-            )?))
+        let identifier = match file.hierarchy() {
+            // Except for the special '\0' and '\1' identifiers
+            Hierarchy::Joliet if identifier.len() > 1 => decode_ucs2(identifier),
+            _ => str::from_utf8(identifier)?.to_string(),
+        };
+        let mut rock_ridge = RockRidge::read(system_use, &file)?;
+
+        // Rock Ridge directory relocation replaces a directory with a
+        // placeholder file with a CL entry, and the '..' entry of the
+        // relocated directory has a PL entry. Present the directory at its
+        // original location instead, with attributes from its '.' entry.
+        let link = rock_ridge
+            .as_ref()
+            .and_then(|x| x.child_link.or(x.parent_link));
+        if let (Some(loc), Some(placeholder)) = (link, &rock_ridge) {
+            let (dot_header, dot_system_use) = read_dot_entry(&file, loc)?;
+            let mut dot_rock_ridge = RockRidge::read(&dot_system_use, &file)?.unwrap_or_default();
+            dot_rock_ridge.name = placeholder.name.clone();
+            header = dot_header;
+            rock_ridge = Some(dot_rock_ridge);
+        }
+
+        // Special files are only recognized by the mode in their PX entry
+        let special = rock_ridge
+            .as_ref()
+            .and_then(|x| x.metadata)
+            .and_then(|x| Some((x, SpecialFileType::from_mode(x.mode)?)));
+
+        match (rock_ridge, special) {
+            (Some(rock_ridge), _) if rock_ridge.symlink.is_some() => Ok(DirectoryEntry::Symlink(
+                ISOSymlink::new(header, identifier, rock_ridge),
+            )),
+            (Some(rock_ridge), Some((metadata, file_type))) => Ok(DirectoryEntry::Special(
+                ISOSpecialFile::new(header, identifier, rock_ridge, metadata, file_type),
+            )),
+            (rock_ridge, _) if header.file_flags.contains(FileFlags::DIRECTORY) => Ok(
+                DirectoryEntry::Directory(ISODirectory::new(header, identifier, rock_ridge, file)),
+            ),
+            (rock_ridge, _) => Ok(DirectoryEntry::File(ISOFile::new(
+                header, identifier, rock_ridge, file,
+            )?)),
         }
     }
 
@@ -36,6 +85,8 @@ impl<T: ISO9660Reader> DirectoryEntry<T> {
         match *self {
             DirectoryEntry::Directory(ref dir) => &dir.header,
             DirectoryEntry::File(ref file) => &file.header,
+            DirectoryEntry::Symlink(ref link) => &link.header,
+            DirectoryEntry::Special(ref special) => &special.header,
         }
     }
 
@@ -43,6 +94,55 @@ impl<T: ISO9660Reader> DirectoryEntry<T> {
         match *self {
             DirectoryEntry::Directory(ref dir) => &dir.identifier,
             DirectoryEntry::File(ref file) => &file.identifier,
+            DirectoryEntry::Symlink(ref link) => &link.identifier,
+            DirectoryEntry::Special(ref special) => &special.identifier,
         }
     }
+
+    pub fn metadata(&self) -> Metadata {
+        match *self {
+            DirectoryEntry::Directory(ref dir) => dir.metadata(),
+            DirectoryEntry::File(ref file) => file.metadata(),
+            DirectoryEntry::Symlink(ref link) => link.metadata(),
+            DirectoryEntry::Special(ref special) => special.metadata(),
+        }
+    }
+
+    /// Whether this is an artifact of Rock Ridge directory relocation: a
+    /// directory that has been moved into the relocation directory, or the
+    /// relocation directory itself. `ISODirectory::contents` skips these,
+    /// since relocated directories appear at their original location.
+    pub fn is_relocated(&self) -> bool {
+        self.rock_ridge().is_some_and(|x| x.relocated)
+    }
+
+    pub fn timestamps(&self) -> Timestamps {
+        self.rock_ridge().map(|x| x.timestamps).unwrap_or_default()
+    }
+
+    pub(crate) fn rock_ridge(&self) -> Option<&RockRidge> {
+        match *self {
+            DirectoryEntry::Directory(ref dir) => dir.rock_ridge.as_ref(),
+            DirectoryEntry::File(ref file) => file.rock_ridge.as_ref(),
+            DirectoryEntry::Symlink(ref link) => Some(&link.rock_ridge),
+            DirectoryEntry::Special(ref special) => Some(&special.rock_ridge),
+        }
+    }
+}
+
+/// Read the header and system use area of the first ('.') entry of the
+/// directory at `loc`, which describes the directory itself.
+pub(crate) fn read_dot_entry<T: ISO9660Reader>(
+    file: &FileRef<T>,
+    loc: u32,
+) -> Result<(DirectoryEntryHeader, Vec<u8>)> {
+    // The '.' entry fits in the first logical block
+    let mut buf = vec![0; file.block_size() as usize];
+    let count = file.read_at(&mut buf, loc as u64)?;
+    if count != buf.len() {
+        return Err(ISOError::ReadSize(buf.len(), count));
+    }
+
+    let (header, _, system_use) = DirectoryEntryHeader::parse(&buf)?;
+    Ok((header, system_use.to_vec()))
 }
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use super::{Metadata, Timestamps};
+use crate::parse::{susp_entries, NameFlags, SuspEntry, SymlinkComponentFlags, TimestampFlags};
+use crate::{FileRef, ISO9660Reader, ISOError, Result};
+
+// Guard against continuation areas that refer back to each other.
+const MAX_CONTINUATIONS: usize = 32;
+
+/// Rock Ridge extensions decoded from the system use area of a directory
+/// record.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RockRidge {
+    pub name: Option<String>,
+    pub symlink: Option<String>,
+    pub metadata: Option<Metadata>,
+    pub timestamps: Timestamps,
+    /// Major and minor device number
+    pub device: Option<(u32, u32)>,
+    /// Location of the directory relocated to the place of this entry
+    pub child_link: Option<u32>,
+    /// Location of the original parent of this relocated directory
+    pub parent_link: Option<u32>,
+    /// Directory relocation has moved this entry elsewhere, or it is the
+    /// relocation directory itself
+    pub relocated: bool,
+}
+
+impl RockRidge {
+    /// Decode the SUSP entries in `system_use`, following any continuation
+    /// areas. Returns `None` if the hierarchy does not use Rock Ridge.
+    /// Malformed entries end the area, keeping the extensions before them.
+    pub fn read<T: ISO9660Reader>(
+        system_use: &[u8],
+        file: &FileRef<T>,
+    ) -> Result<Option<RockRidge>> {
+        let skip = match file.susp_skip() {
+            Some(skip) => skip as usize,
+            None => return Ok(None),
+        };
+
+        let mut entries = susp_entries(system_use.get(skip..).unwrap_or(&[]));
+        let mut rock_ridge = RockRidge::default();
+        let mut name = None;
+        let mut symlink = None;
+        // Whether the last symlink component continues in the next one
+        let mut symlink_continue = false;
+        let mut continuations = 0;
+
+        while !entries.is_empty() {
+            let mut continuation = None;
+
+            for entry in entries {
+                match entry {
+                    SuspEntry::ContinuationArea {
+                        block,
+                        offset,
+                        length,
+                    } => continuation = Some((block, offset, length)),
+                    SuspEntry::AlternateName { flags, name: part } => {
+                        if flags.contains(NameFlags::CURRENT) {
+                            name = Some(b".".to_vec());
+                        } else if flags.contains(NameFlags::PARENT) {
+                            name = Some(b"..".to_vec());
+                        } else {
+                            name.get_or_insert_with(Vec::new).extend(part);
+                        }
+                    }
+                    SuspEntry::SymbolicLink { components } => {
+                        let target = symlink.get_or_insert_with(Vec::new);
+                        for (flags, content) in components {
+                            if !symlink_continue && !target.is_empty() && !target.ends_with(b"/") {
+                                target.push(b'/');
+                            }
+                            if flags.contains(SymlinkComponentFlags::ROOT) {
+                                target.push(b'/');
+                            } else if flags.contains(SymlinkComponentFlags::CURRENT) {
+                                target.push(b'.');
+                            } else if flags.contains(SymlinkComponentFlags::PARENT) {
+                                target.extend(b"..");
+                            } else {
+                                target.extend(content);
+                            }
+                            symlink_continue = flags.contains(SymlinkComponentFlags::CONTINUE);
+                        }
+                    }
+                    SuspEntry::PosixAttributes {
+                        mode,
+                        links,
+                        uid,
+                        gid,
+                        serial,
+                    } => {
+                        rock_ridge.metadata = Some(Metadata {
+                            mode,
+                            nlink: links,
+                            uid,
+                            gid,
+                            serial,
+                        })
+                    }
+                    SuspEntry::DeviceNumber { high, low } => {
+                        // Like Linux, accept writers that store a whole
+                        // old style `dev_t` in the low word.
+                        rock_ridge.device = if high == 0 && low & !0xff != 0 {
+                            Some((low >> 8, low & 0xff))
+                        } else {
+                            Some((high, low))
+                        };
+                    }
+                    SuspEntry::ChildLink { loc } => rock_ridge.child_link = Some(loc),
+                    SuspEntry::ParentLink { loc } => rock_ridge.parent_link = Some(loc),
+                    SuspEntry::Relocated => rock_ridge.relocated = true,
+                    SuspEntry::Timestamps { flags, times } => {
+                        let mut times = times.into_iter();
+                        let timestamps = &mut rock_ridge.timestamps;
+                        for (flag, timestamp) in [
+                            (TimestampFlags::CREATION, &mut timestamps.creation),
+                            (TimestampFlags::MODIFY, &mut timestamps.modify),
+                            (TimestampFlags::ACCESS, &mut timestamps.access),
+                            (TimestampFlags::ATTRIBUTES, &mut timestamps.attributes),
+                            (TimestampFlags::BACKUP, &mut timestamps.backup),
+                            (TimestampFlags::EXPIRATION, &mut timestamps.expiration),
+                            (TimestampFlags::EFFECTIVE, &mut timestamps.effective),
+                        ] {
+                            if flags.contains(flag) {
+                                *timestamp = times.next();
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            entries = match continuation {
+                Some(_) if continuations >= MAX_CONTINUATIONS => {
+                    return Err(ISOError::InvalidFs("Too many SUSP continuation areas"));
+                }
+                Some((block, offset, length)) => {
+                    continuations += 1;
+                    let area = read_continuation_area(file, block, offset, length)?;
+                    susp_entries(&area)
+                }
+                None => Vec::new(),
+            };
+        }
+
+        rock_ridge.name = name.map(|x| String::from_utf8_lossy(&x).into_owned());
+        rock_ridge.symlink = symlink.map(|x| String::from_utf8_lossy(&x).into_owned());
+
+        Ok(Some(rock_ridge))
+    }
+}
+
+fn read_continuation_area<T: ISO9660Reader>(
+    file: &FileRef<T>,
+    block: u32,
+    offset: u32,
+    length: u32,
+) -> Result<Vec<u8>> {
+    let (offset, length) = (offset as usize, length as usize);
+    let block_size = file.block_size() as usize;
+    let block_count = (offset + length).div_ceil(block_size);
+    let mut buf = vec![0; block_count * block_size];
+
+    let count = file.read_at(&mut buf, block as u64)?;
+    if count != buf.len() {
+        return Err(ISOError::ReadSize(buf.len(), count));
+    }
+
+    buf.truncate(offset + length);
+    buf.drain(..offset);
+    Ok(buf)
+}
@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use crate::cue::CueSheet;
+use crate::raw_sector::FORM2_DATA_SIZE;
+use crate::{ccd, mds, nrg};
+use crate::{
+    ISO9660Reader, ISOError, OffsetReader, RawSectorReader, Result, SectorLayout, SectorStatus,
+    WavReader,
+};
+
+/// What a track holds, and how its sectors are stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackMode {
+    /// CD-DA audio, in 2352 byte sectors
+    Audio,
+    /// CD+G audio, with 96 bytes of subchannel data after each sector
+    Cdg,
+    /// Mode 1 data, with the given sector size (2048 or 2352, or 2448 with
+    /// subchannel data)
+    Mode1(u16),
+    /// Mode 2 data, with the given sector size (2048, 2324, 2336 or 2352,
+    /// or 2448 with subchannel data)
+    Mode2(u16),
+    /// CD-i data, with the given sector size (2336 or 2352)
+    Cdi(u16),
+}
+
+impl TrackMode {
+    /// Parse a track mode as written in a CUE sheet, like `MODE1/2352`
+    pub fn parse(mode: &str) -> Result<TrackMode> {
+        let mode = mode.to_ascii_uppercase();
+        let (name, size) = match mode.split_once('/') {
+            Some((name, size)) => (name, Some(size.parse()?)),
+            None => (mode.as_str(), None),
+        };
+        Ok(match (name, size) {
+            ("AUDIO", None) => TrackMode::Audio,
+            ("CDG", None) => TrackMode::Cdg,
+            ("MODE1", Some(size @ (2048 | 2352))) => TrackMode::Mode1(size),
+            ("MODE2", Some(size @ (2048 | 2324 | 2336 | 2352))) => TrackMode::Mode2(size),
+            ("CDI", Some(size @ (2336 | 2352))) => TrackMode::Cdi(size),
+            _ => return Err(ISOError::InvalidFs("Unknown track mode")),
+        })
+    }
+
+    pub fn is_data(self) -> bool {
+        !matches!(self, TrackMode::Audio | TrackMode::Cdg)
+    }
+
+    /// Size of each stored sector
+    pub fn sector_size(self) -> usize {
+        match self {
+            TrackMode::Audio => 2352,
+            TrackMode::Cdg => 2448,
+            TrackMode::Mode1(size) | TrackMode::Mode2(size) | TrackMode::Cdi(size) => size as usize,
+        }
+    }
+
+    /// Layout of the sectors, and whether they are followed by subchannel
+    /// data, for tracks that can hold a filesystem
+    fn layout(self) -> Option<(SectorLayout, bool)> {
+        match self.sector_size() {
+            _ if !self.is_data() => None,
+            2048 => Some((SectorLayout::Cooked, false)),
+            2336 => Some((SectorLayout::Raw2336, false)),
+            2352 => Some((SectorLayout::Raw2352, false)),
+            2448 => Some((SectorLayout::Raw2352, true)),
+            // Mode 2 Form 2 only, which has no room for 2048 bytes of data
+            _ => None,
+        }
+    }
+}
+
+/// A track of a disc image, and where it is stored
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Track {
+    pub number: u8,
+    /// Session of the disc the track belongs to, starting at 1
+    pub session: u8,
+    pub mode: TrackMode,
+    /// Index of the file the track is stored in, in `Disc::files`
+    pub file: usize,
+    /// Byte offset in the file of the start of the track, at index 1
+    pub offset: u64,
+    /// Sector of the disc where the track starts, at index 1
+    pub start: u64,
+    /// Sectors of pregap before `start`, whether stored in the file or not
+    pub pregap: u64,
+    /// Sectors of the pregap that are stored in the file, before `offset`
+    pub stored_pregap: u64,
+    /// Number of sectors stored from `start`
+    pub length: u64,
+}
+
+/// The layout of a disc image whose tracks may be spread over several
+/// files, like BIN/CUE images, or described by a single descriptor, like
+/// NRG, MDS/MDF and CCD/IMG images
+#[derive(Clone, Debug)]
+pub struct Disc {
+    files: Vec<PathBuf>,
+    tracks: Vec<Track>,
+}
+
+impl Disc {
+    /// Read a CUE sheet and the size of the files it refers to, which are
+    /// found relative to it
+    pub fn open_cue<P: AsRef<Path>>(path: P) -> Result<Disc> {
+        let path = path.as_ref();
+        let sheet = CueSheet::parse(&fs::read_to_string(path)?)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let files = sheet
+            .files
+            .iter()
+            .map(|x| dir.join(&x.name))
+            .collect::<Vec<_>>();
+        let sizes = files
+            .iter()
+            .map(|x| Ok(fs::metadata(x)?.len()))
+            .collect::<Result<Vec<_>>>()?;
+        let tracks = sheet.tracks(&sizes)?;
+
+        Ok(Disc { files, tracks })
+    }
+
+    /// Read the tracks of a Nero NRG image
+    pub fn open_nrg<P: AsRef<Path>>(path: P) -> Result<Disc> {
+        let path = path.as_ref();
+        let tracks = nrg::read_tracks(&mut File::open(path)?)?;
+        Ok(Disc {
+            files: vec![path.to_path_buf()],
+            tracks,
+        })
+    }
+
+    /// Read the tracks of an Alcohol 120% MDS descriptor, stored in the MDF
+    /// file it names
+    pub fn open_mds<P: AsRef<Path>>(path: P) -> Result<Disc> {
+        let path = path.as_ref();
+        let (tracks, file_name) = mds::read_tracks(&fs::read(path)?)?;
+        let file = match file_name {
+            Some(name) => path.parent().unwrap_or_else(|| Path::new("")).join(name),
+            None => path.with_extension("mdf"),
+        };
+        Ok(Disc {
+            files: vec![file],
+            tracks,
+        })
+    }
+
+    /// Read the tracks of a CloneCD CCD file, stored in the IMG file of the
+    /// same name
+    pub fn open_ccd<P: AsRef<Path>>(path: P) -> Result<Disc> {
+        let path = path.as_ref();
+        let file = path.with_extension("img");
+        let tracks = ccd::read_tracks(&fs::read_to_string(path)?, fs::metadata(&file)?.len())?;
+        Ok(Disc {
+            files: vec![file],
+            tracks,
+        })
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn track(&self, number: u8) -> Option<&Track> {
+        self.tracks.iter().find(|x| x.number == number)
+    }
+
+    pub fn first_data_track(&self) -> Option<&Track> {
+        self.tracks.iter().find(|x| x.mode.is_data())
+    }
+
+    /// Start sector of each session with a data track, from the table of
+    /// contents. Unlike `ISO9660::find_sessions`, this doesn't rely on
+    /// finding volume descriptors between sessions.
+    pub fn session_starts(&self) -> Vec<u64> {
+        let mut starts = Vec::<(u8, u64)>::new();
+        for track in self.tracks.iter().filter(|x| x.mode.is_data()) {
+            if starts.last().map(|x| x.0) != Some(track.session) {
+                starts.push((track.session, track.start));
+            }
+        }
+        starts.into_iter().map(|x| x.1).collect()
+    }
+
+    pub fn audio_tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.iter().filter(|x| !x.mode.is_data())
+    }
+
+    /// Open the data track with the given number, to read its filesystem
+    pub fn open_track(&self, number: u8) -> Result<TrackReader<File>> {
+        let track = self
+            .track(number)
+            .ok_or(ISOError::InvalidFs("No such track"))?;
+        TrackReader::new(File::open(&self.files[track.file])?, track)
+    }
+
+    /// Open the first data track, which holds the filesystem of most discs
+    pub fn open_data_track(&self) -> Result<TrackReader<File>> {
+        let track = self
+            .first_data_track()
+            .ok_or(ISOError::InvalidFs("No data track"))?;
+        TrackReader::new(File::open(&self.files[track.file])?, track)
+    }
+
+    /// Open the audio track with the given number as a WAV file, including
+    /// its pregap if `pregap` is set
+    pub fn open_audio_track(&self, number: u8, pregap: bool) -> Result<WavReader<File>> {
+        let track = self
+            .track(number)
+            .ok_or(ISOError::InvalidFs("No such track"))?;
+        WavReader::new(File::open(&self.files[track.file])?, track, pregap)
+    }
+}
+
+/// Reads the sectors of a data track. Sectors are addressed from the start
+/// of the disc, as the filesystem does, so a track that doesn't start the
+/// disc, like the data track in the second session of an Enhanced CD, is
+/// opened with `ISO9660::new_track`.
+#[derive(Debug)]
+pub struct TrackReader<T: Read + Seek> {
+    inner: RawSectorReader<OffsetReader<T>>,
+    start: u64,
+    length: u64,
+}
+
+impl<T: Read + Seek> TrackReader<T> {
+    /// Read `track` from the file it is stored in
+    pub fn new(file: T, track: &Track) -> Result<TrackReader<T>> {
+        let (layout, subchannel) = track
+            .mode
+            .layout()
+            .ok_or(ISOError::InvalidFs("Not a data track"))?;
+        let inner = OffsetReader::new(file, track.offset);
+        Ok(TrackReader {
+            inner: RawSectorReader::with_layout(inner, layout, subchannel),
+            start: track.start,
+            length: track.length,
+        })
+    }
+
+    /// Sector of the disc where the track starts
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Repair damaged sectors as they are read, as with
+    /// `RawSectorReader::set_error_correction`
+    pub fn set_error_correction(&mut self, error_correction: bool) {
+        self.inner.set_error_correction(error_correction);
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+impl<T: Read + Seek> ISO9660Reader for TrackReader<T> {
+    fn read_at(&mut self, buf: &mut [u8], lba: u64) -> io::Result<usize> {
+        let sector = lba
+            .checked_sub(self.start)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Sector before track"))?;
+        // Don't read into the sectors of the next track
+        let sectors = self.length.saturating_sub(sector);
+        let len = buf
+            .len()
+            .min((sectors * 2048).min(usize::MAX as u64) as usize);
+        self.inner.read_at(&mut buf[..len], sector)
+    }
+
+    fn sector_status(&mut self, lba: u64) -> io::Result<SectorStatus> {
+        match lba.checked_sub(self.start) {
+            Some(sector) if sector < self.length => self.inner.sector_status(sector),
+            // The track ends before the sector
+            Some(_) => Ok(SectorStatus::Missing),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Sector before track",
+            )),
+        }
+    }
+
+    fn has_raw_sectors(&self) -> bool {
+        self.inner.has_raw_sectors()
+    }
+
+    fn read_form2_at(&mut self, buf: &mut [u8], lba: u64) -> io::Result<usize> {
+        let sector = lba
+            .checked_sub(self.start)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Sector before track"))?;
+        let sectors = self.length.saturating_sub(sector);
+        let len = buf
+            .len()
+            .min((sectors * FORM2_DATA_SIZE as u64).min(usize::MAX as u64) as usize);
+        self.inner.read_form2_at(&mut buf[..len], sector)
+    }
+}
@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+//! Error detection (EDC) and correction (ECC) codes of raw CD-ROM sectors,
+//! as described in ECMA-130
+
+const EDC_POLY: u32 = 0xd801_8001;
+// Primitive polynomial of the Galois field GF(2^8) of the Reed-Solomon codes
+const GF_POLY: u16 = 0x11d;
+// How many times the P and Q codes are alternately corrected. Each pass
+// can fix errors the other couldn't, as long as there are few enough.
+const CORRECTION_ROUNDS: usize = 4;
+
+static EDC_TABLE: [u32; 256] = edc_table();
+static GF_EXP: [u8; 255] = gf_tables().0;
+static GF_LOG: [u8; 256] = gf_tables().1;
+
+/// What the error detection and correction codes of a sector say about it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectorStatus {
+    /// The sector has no codes to check, like the sectors of an ISO file or
+    /// of Mode 2 Form 2
+    Unchecked,
+    Good,
+    /// The sector is damaged, but its ECC can repair it
+    Correctable,
+    /// The sector is damaged beyond what its ECC can repair
+    Uncorrectable,
+    /// The image ends before the sector
+    Missing,
+}
+
+const fn edc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut edc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            edc = (edc >> 1) ^ if edc & 1 != 0 { EDC_POLY } else { 0 };
+            bit += 1;
+        }
+        table[i] = edc;
+        i += 1;
+    }
+    table
+}
+
+const fn gf_tables() -> ([u8; 255], [u8; 256]) {
+    let mut exp = [0; 255];
+    let mut log = [0; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= GF_POLY;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    GF_EXP[(GF_LOG[a as usize] as usize + GF_LOG[b as usize] as usize) % 255]
+}
+
+fn edc(data: &[u8]) -> u32 {
+    data.iter().fold(0, |edc, &byte| {
+        (edc >> 8) ^ EDC_TABLE[((edc ^ byte as u32) & 0xff) as usize]
+    })
+}
+
+/// One of the two Reed-Solomon product codes protecting a sector. Each of
+/// its codewords takes bytes at a regular interval from the header onwards,
+/// followed by two parity bytes, and can correct a single byte error.
+struct Code {
+    codewords: usize,
+    data_len: usize,
+    codeword_step: usize,
+    byte_step: usize,
+    /// Offset of the parity bytes from the header
+    parity: usize,
+}
+
+const P_CODE: Code = Code {
+    codewords: 86,
+    data_len: 24,
+    codeword_step: 2,
+    byte_step: 86,
+    parity: 0x81c - 12,
+};
+
+const Q_CODE: Code = Code {
+    codewords: 52,
+    data_len: 43,
+    codeword_step: 86,
+    byte_step: 88,
+    parity: 0x8c8 - 12,
+};
+
+impl Code {
+    /// Offsets from the header of the bytes of a codeword, parity last
+    fn positions(&self, codeword: usize) -> Vec<usize> {
+        let size = self.codewords * self.data_len;
+        let mut index = (codeword >> 1) * self.codeword_step + (codeword & 1);
+        let mut positions = Vec::with_capacity(self.data_len + 2);
+        for _ in 0..self.data_len {
+            positions.push(index);
+            index += self.byte_step;
+            if index >= size {
+                index -= size;
+            }
+        }
+        positions.push(self.parity + codeword);
+        positions.push(self.parity + self.codewords + codeword);
+        positions
+    }
+
+    /// The two syndromes of a codeword, which are zero if it is intact
+    fn syndromes(data: &[u8], positions: &[usize]) -> (u8, u8) {
+        positions.iter().fold((0, 0), |(s0, s1), &i| {
+            (s0 ^ data[i], gf_mul(s1, 2) ^ data[i])
+        })
+    }
+
+    fn is_valid(&self, data: &[u8]) -> bool {
+        (0..self.codewords).all(|x| Code::syndromes(data, &self.positions(x)) == (0, 0))
+    }
+
+    /// Correct a single byte error in each codeword. Returns whether every
+    /// codeword is now intact.
+    fn correct(&self, data: &mut [u8]) -> bool {
+        let mut valid = true;
+        for codeword in 0..self.codewords {
+            let positions = self.positions(codeword);
+            let (s0, s1) = Code::syndromes(data, &positions);
+            if (s0, s1) == (0, 0) {
+                continue;
+            }
+            if s0 == 0 || s1 == 0 {
+                valid = false;
+                continue;
+            }
+
+            // An error of value s0 at position i gives s1 = s0 * 2^(n - 1 - i)
+            let power = (GF_LOG[s1 as usize] as usize + 255 - GF_LOG[s0 as usize] as usize) % 255;
+            match positions.len().checked_sub(power + 1) {
+                Some(i) => data[positions[i]] ^= s0,
+                None => valid = false,
+            }
+        }
+        valid
+    }
+}
+
+/// Check the EDC and ECC of a whole 2352 byte sector, repairing it if
+/// `correct` is set and its ECC allows. Only Mode 1 and Mode 2 Form 1
+/// sectors have codes to check.
+pub(crate) fn check(sector: &mut [u8], correct: bool) -> SectorStatus {
+    let (edc_range, edc_loc) = match (sector[15], sector[18] & 0x20) {
+        (1, _) => (0..0x810, 0x810),
+        // Form 1, which has ECC
+        (2, 0) => (16..0x818, 0x818),
+        _ => return SectorStatus::Unchecked,
+    };
+    let mode2 = sector[15] == 2;
+
+    let mut work = sector.to_vec();
+    // The header is not protected by the ECC of Mode 2 sectors, which is
+    // computed as if it was zero
+    let header = [work[12], work[13], work[14], work[15]];
+    if mode2 {
+        work[12..16].fill(0);
+    }
+
+    let is_valid = |work: &[u8]| {
+        let stored = u32::from_le_bytes([
+            work[edc_loc],
+            work[edc_loc + 1],
+            work[edc_loc + 2],
+            work[edc_loc + 3],
+        ]);
+        edc(&work[edc_range.clone()]) == stored
+            && P_CODE.is_valid(&work[12..])
+            && Q_CODE.is_valid(&work[12..])
+    };
+    if is_valid(&work) {
+        return SectorStatus::Good;
+    }
+
+    for _ in 0..CORRECTION_ROUNDS {
+        let p_valid = P_CODE.correct(&mut work[12..]);
+        let q_valid = Q_CODE.correct(&mut work[12..]);
+        if p_valid && q_valid {
+            break;
+        }
+    }
+    if !is_valid(&work) {
+        return SectorStatus::Uncorrectable;
+    }
+
+    if correct {
+        if mode2 {
+            work[12..16].copy_from_slice(&header);
+        }
+        sector.copy_from_slice(&work);
+    }
+    SectorStatus::Correctable
+}
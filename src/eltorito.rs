@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+//! El Torito bootable CD-ROM format
+
+use crate::parse::{boot_entry, section_header, validation_entry};
+use crate::{FileRef, ISO9660Reader, ISOError, Result};
+
+pub(crate) const EL_TORITO_IDENTIFIER: &str = "EL TORITO SPECIFICATION";
+
+// The catalog normally fits in one sector, but nothing limits its size;
+// this guards against a catalog without a final section header.
+const MAX_CATALOG_SECTORS: u64 = 32;
+
+/// Platform a boot image is meant for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    X86,
+    PowerPC,
+    Mac,
+    Efi,
+    Other(u8),
+}
+
+impl From<u8> for Platform {
+    fn from(id: u8) -> Platform {
+        match id {
+            0 => Platform::X86,
+            1 => Platform::PowerPC,
+            2 => Platform::Mac,
+            0xef => Platform::Efi,
+            id => Platform::Other(id),
+        }
+    }
+}
+
+/// How the BIOS presents a boot image to the booted system
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Emulation {
+    NoEmulation,
+    Floppy1200K,
+    Floppy1440K,
+    Floppy2880K,
+    HardDisk,
+    Other(u8),
+}
+
+impl From<u8> for Emulation {
+    fn from(media_type: u8) -> Emulation {
+        match media_type {
+            0 => Emulation::NoEmulation,
+            1 => Emulation::Floppy1200K,
+            2 => Emulation::Floppy1440K,
+            3 => Emulation::Floppy2880K,
+            4 => Emulation::HardDisk,
+            media_type => Emulation::Other(media_type),
+        }
+    }
+}
+
+/// The initial/default entry, or an entry of a section
+#[derive(Clone, Debug)]
+pub struct BootEntry {
+    pub bootable: bool,
+    pub emulation: Emulation,
+    /// Real mode segment the image is loaded at; 0 means the traditional
+    /// 0x7C0
+    pub load_segment: u16,
+    /// Partition type of the image, for hard disk emulation
+    pub system_type: u8,
+    /// Number of 512 byte virtual sectors loaded by the BIOS
+    pub sector_count: u16,
+    /// Location of the image, in 2048 byte sectors
+    pub load_rba: u32,
+}
+
+impl BootEntry {
+    /// Size of the boot image in bytes. This is the size of the floppy for
+    /// floppy emulation, and otherwise the part loaded by the BIOS, which
+    /// may be less than the whole image.
+    pub fn image_size(&self) -> u64 {
+        match self.emulation {
+            Emulation::Floppy1200K => 1200 * 1024,
+            Emulation::Floppy1440K => 1440 * 1024,
+            Emulation::Floppy2880K => 2880 * 1024,
+            _ => self.sector_count as u64 * 512,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BootSection {
+    pub platform: Platform,
+    pub identifier: String,
+    pub entries: Vec<BootEntry>,
+}
+
+/// El Torito boot catalog, as pointed to by the boot record volume
+/// descriptor
+#[derive(Clone, Debug)]
+pub struct BootCatalog {
+    /// Platform of the default entry
+    pub platform: Platform,
+    /// Manufacturer of the CD-ROM, from the validation entry
+    pub manufacturer: String,
+    pub default_entry: BootEntry,
+    pub sections: Vec<BootSection>,
+}
+
+impl BootCatalog {
+    pub(crate) fn read<T: ISO9660Reader>(file: &FileRef<T>, loc: u32) -> Result<BootCatalog> {
+        let mut catalog = CatalogReader {
+            file,
+            loc,
+            buf: Vec::new(),
+        };
+
+        let validation = catalog.entry(0)?;
+        let checksum = validation.chunks_exact(2).fold(0u16, |sum, x| {
+            sum.wrapping_add(u16::from_le_bytes([x[0], x[1]]))
+        });
+        if checksum != 0 {
+            return Err(ISOError::InvalidFs("Invalid El Torito validation entry"));
+        }
+        let (platform, manufacturer) = validation_entry(validation)?.1;
+        let default_entry = boot_entry(catalog.entry(1)?)?.1;
+
+        let mut sections = Vec::new();
+        let mut index = 2;
+        loop {
+            let header = catalog.entry(index)?;
+            if header[0] != 0x90 && header[0] != 0x91 {
+                break;
+            }
+            let (last, platform, entry_count, identifier) = section_header(header)?.1;
+            index += 1;
+
+            let mut entries = Vec::new();
+            for _ in 0..entry_count {
+                entries.push(boot_entry(catalog.entry(index)?)?.1);
+                index += 1;
+                // Skip section entry extensions
+                while catalog.entry(index)?[0] == 0x44 {
+                    index += 1;
+                }
+            }
+
+            sections.push(BootSection {
+                platform,
+                identifier,
+                entries,
+            });
+
+            if last {
+                break;
+            }
+        }
+
+        Ok(BootCatalog {
+            platform,
+            manufacturer,
+            default_entry,
+            sections,
+        })
+    }
+
+    /// All entries, starting with the default entry
+    pub fn entries(&self) -> impl Iterator<Item = &BootEntry> {
+        std::iter::once(&self.default_entry).chain(self.sections.iter().flat_map(|x| &x.entries))
+    }
+}
+
+// Reads the sectors of the catalog as its entries are needed
+struct CatalogReader<'a, T: ISO9660Reader> {
+    file: &'a FileRef<T>,
+    loc: u32,
+    buf: Vec<u8>,
+}
+
+impl<T: ISO9660Reader> CatalogReader<'_, T> {
+    fn entry(&mut self, index: usize) -> Result<&[u8]> {
+        while self.buf.len() < (index + 1) * 32 {
+            let sector = (self.buf.len() / 2048) as u64;
+            if sector >= MAX_CATALOG_SECTORS {
+                return Err(ISOError::InvalidFs("El Torito boot catalog too long"));
+            }
+
+            let mut buf = [0; 2048];
+            let lba = (self.loc as u64 + sector) * self.file.blocks_per_sector();
+            let count = self.file.read_at(&mut buf, lba)?;
+            if count != 2048 {
+                return Err(ISOError::ReadSize(2048, count));
+            }
+            self.buf.extend_from_slice(&buf);
+        }
+        Ok(&self.buf[index * 32..(index + 1) * 32])
+    }
+}
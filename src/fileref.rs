@@ -1,14 +1,39 @@
 // SPDX-License-Identifier: (MIT OR Apache-2.0)
 
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
 #[cfg(feature = "nightly")]
 use std::fs::File;
-use std::io::{Read, Result, Seek, SeekFrom};
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
 use std::rc::Rc;
 
+use crate::SectorStatus;
+
 pub trait ISO9660Reader {
-    /// Read the block(s) at a given LBA (logical block address)
+    /// Read the 2048 byte sector(s) at a given LBA (logical block address).
+    /// Volumes with a smaller logical block size are read in terms of
+    /// these sectors.
     fn read_at(&mut self, buf: &mut [u8], lba: u64) -> Result<usize>;
+
+    /// Check the error detection and correction codes of the sector at a
+    /// given LBA. Only readers of raw sectors have codes to check.
+    fn sector_status(&mut self, _lba: u64) -> Result<SectorStatus> {
+        Ok(SectorStatus::Unchecked)
+    }
+
+    /// Whether the reader has whole sectors, so `read_form2_at` can read
+    /// Mode 2 Form 2 sectors
+    fn has_raw_sectors(&self) -> bool {
+        false
+    }
+
+    /// Read the 2324 bytes of data of each Mode 2 Form 2 sector at a given
+    /// LBA. Images of 2048 byte sectors don't store them.
+    fn read_form2_at(&mut self, _buf: &mut [u8], _lba: u64) -> Result<usize> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "Mode 2 Form 2 sectors need raw sectors",
+        ))
+    }
 }
 
 #[cfg(not(feature = "nightly"))]
@@ -44,22 +69,129 @@ impl ISO9660Reader for File {
     }
 }
 
+/// The directory hierarchies an image can contain, each starting at the
+/// root directory of a different volume descriptor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Hierarchy {
+    Primary,
+    /// Joliet, with UCS-2 identifiers
+    Joliet,
+    /// From an enhanced volume descriptor (ISO 9660:1999), with relaxed
+    /// identifiers that have no version number
+    Enhanced,
+}
+
 // TODO: Figure out if sane API possible without Rc/RefCell
-pub(crate) struct FileRef<T: ISO9660Reader>(Rc<RefCell<T>>);
+pub(crate) struct FileRef<T: ISO9660Reader> {
+    reader: Rc<RefCell<T>>,
+    hierarchy: Hierarchy,
+    /// Number of bytes to skip at the start of each system use area, if
+    /// the hierarchy uses the System Use Sharing Protocol (Rock Ridge)
+    susp_skip: Option<u8>,
+    /// Location of the root directory of the hierarchy
+    root_loc: u32,
+    /// Logical block size of the volume; 512, 1024 or 2048
+    block_size: u16,
+}
 
 impl<T: ISO9660Reader> Clone for FileRef<T> {
     fn clone(&self) -> FileRef<T> {
-        FileRef(self.0.clone())
+        FileRef {
+            reader: self.reader.clone(),
+            hierarchy: self.hierarchy,
+            susp_skip: self.susp_skip,
+            root_loc: self.root_loc,
+            block_size: self.block_size,
+        }
     }
 }
 
 impl<T: ISO9660Reader> FileRef<T> {
     pub fn new(reader: T) -> FileRef<T> {
-        FileRef(Rc::new(RefCell::new(reader)))
+        FileRef {
+            reader: Rc::new(RefCell::new(reader)),
+            hierarchy: Hierarchy::Primary,
+            susp_skip: None,
+            root_loc: 0,
+            block_size: 2048,
+        }
     }
 
-    /// Read the block(s) at a given LBA (logical block address)
+    /// Read the block(s) at a given LBA (logical block address), in units of
+    /// the logical block size of the volume
     pub fn read_at(&self, buf: &mut [u8], lba: u64) -> Result<usize> {
-        (*self.0).borrow_mut().read_at(buf, lba)
+        let mut reader = (*self.reader).borrow_mut();
+        if self.block_size == 2048 {
+            return reader.read_at(buf, lba);
+        }
+
+        // Read the sectors containing the blocks
+        let offset = lba * self.block_size as u64;
+        let start = (offset % 2048) as usize;
+        let mut sectors = vec![0; (start + buf.len()).div_ceil(2048) * 2048];
+        let count = reader.read_at(&mut sectors, offset / 2048)?;
+        let count = count.saturating_sub(start).min(buf.len());
+        buf[..count].copy_from_slice(&sectors[start..start + count]);
+        Ok(count)
+    }
+
+    pub fn reader(&self) -> RefMut<'_, T> {
+        self.reader.borrow_mut()
+    }
+
+    /// A reference to the same reader, without the state of any hierarchy
+    /// but the logical block size of the volume
+    pub fn shared(&self) -> FileRef<T> {
+        FileRef {
+            reader: self.reader.clone(),
+            hierarchy: Hierarchy::Primary,
+            susp_skip: None,
+            root_loc: 0,
+            block_size: self.block_size,
+        }
+    }
+
+    pub fn hierarchy(&self) -> Hierarchy {
+        self.hierarchy
+    }
+
+    /// A reference to the same reader, for a different hierarchy
+    pub fn with_hierarchy(&self, hierarchy: Hierarchy, root_loc: u32) -> FileRef<T> {
+        FileRef {
+            reader: self.reader.clone(),
+            hierarchy,
+            susp_skip: None,
+            root_loc,
+            block_size: self.block_size,
+        }
+    }
+
+    pub fn susp_skip(&self) -> Option<u8> {
+        self.susp_skip
+    }
+
+    pub fn set_susp_skip(&mut self, skip: Option<u8>) {
+        self.susp_skip = skip;
+    }
+
+    pub fn root_loc(&self) -> u32 {
+        self.root_loc
+    }
+
+    pub fn set_root_loc(&mut self, loc: u32) {
+        self.root_loc = loc;
+    }
+
+    pub fn block_size(&self) -> u16 {
+        self.block_size
+    }
+
+    pub fn set_block_size(&mut self, block_size: u16) {
+        self.block_size = block_size;
+    }
+
+    /// Number of logical blocks in a 2048 byte sector
+    pub fn blocks_per_sector(&self) -> u64 {
+        2048 / self.block_size as u64
     }
 }
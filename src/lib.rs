@@ -7,27 +7,77 @@ extern crate time;
 extern crate bitflags;
 extern crate nom;
 
+use std::io::{Read, Seek};
 use std::result;
 
+use time::OffsetDateTime;
+
+pub use audio::WavReader;
+pub use cso::{Compression, CsoReader};
+pub use cue::{CueFile, CueSheet, CueTrack};
+use directory_entry::{read_dot_entry, RockRidge};
 pub use directory_entry::{
-    DirectoryEntry, ISODirectory, ISODirectoryIterator, ISOFile, ISOFileReader,
+    DirectoryEntry, ExtendedAttributes, ISODirectory, ISODirectoryIterator, ISOFile, ISOFileReader,
+    ISOSpecialFile, ISOSymlink, Metadata, SpecialFileType, Timestamps,
 };
+pub use disc::{Disc, Track, TrackMode, TrackReader};
+pub use ecc::SectorStatus;
+use eltorito::EL_TORITO_IDENTIFIER;
+pub use eltorito::{BootCatalog, BootEntry, BootSection, Emulation, Platform};
 pub use error::ISOError;
-pub(crate) use fileref::FileRef;
 pub use fileref::ISO9660Reader;
-use parse::VolumeDescriptor;
+pub(crate) use fileref::{FileRef, Hierarchy};
+pub use offset::OffsetReader;
+use parse::{susp_entries, SuspEntry, VolumeDescriptor};
+pub use parse::{XaAttributes, XaRecord};
+pub use path_table::{PathTable, PathTableEntry, PathTableType};
+pub use raw_sector::{RawSectorReader, SectorLayout};
+pub use session::Change;
+pub use verify::BadSector;
+pub use volume_partition::VolumePartition;
 
 pub type Result<T> = result::Result<T, ISOError>;
 
+// Same limit as Linux's MAXSYMLINKS
+const MAX_SYMLINKS: usize = 40;
+// Sectors read at a time when looking for the next session
+const SESSION_SCAN_SECTORS: usize = 32;
+
+mod audio;
+mod ccd;
+mod cso;
+mod cue;
 mod directory_entry;
+mod disc;
+mod ecc;
+mod eltorito;
 mod error;
 mod fileref;
+mod mds;
+mod nrg;
+mod offset;
 mod parse;
+mod path_table;
+mod raw_sector;
+mod session;
+mod verify;
+mod volume_partition;
 
 pub struct ISO9660<T: ISO9660Reader> {
-    _file: FileRef<T>,
+    file: FileRef<T>,
     pub root: ISODirectory<T>,
+    /// Root of the Joliet hierarchy, if the image has one
+    pub joliet_root: Option<ISODirectory<T>>,
+    /// Root of the hierarchy of the enhanced volume descriptor (ISO
+    /// 9660:1999), if the image has one
+    pub enhanced_root: Option<ISODirectory<T>>,
     primary: VolumeDescriptor,
+    joliet_level: Option<u8>,
+    boot_catalog_loc: Option<u32>,
+    /// Path table of the primary hierarchy, used to speed up lookups
+    path_table: Option<PathTable>,
+    partitions: Vec<VolumePartition>,
+    session_start: u64,
 }
 
 macro_rules! primary_prop_str {
@@ -42,18 +92,47 @@ macro_rules! primary_prop_str {
     };
 }
 
+macro_rules! primary_prop_time {
+    ($name:ident) => {
+        pub fn $name(&self) -> OffsetDateTime {
+            if let VolumeDescriptor::Primary { $name, .. } = &self.primary {
+                *$name
+            } else {
+                unreachable!()
+            }
+        }
+    };
+}
+
 impl<T: ISO9660Reader> ISO9660<T> {
-    pub fn new(mut reader: T) -> Result<ISO9660<T>> {
+    pub fn new(reader: T) -> Result<ISO9660<T>> {
+        ISO9660::new_session(reader, 0)
+    }
+
+    /// Open the session of a multi-session image that starts at the given
+    /// sector, as reported by `find_sessions` or `cdrecord -msinfo`. Extent
+    /// locations are absolute, so a session can refer to files written by
+    /// earlier ones.
+    pub fn new_session(reader: T, session_start: u64) -> Result<ISO9660<T>> {
+        ISO9660::open_session(FileRef::new(reader), session_start)
+    }
+
+    fn open_session(mut file: FileRef<T>, session_start: u64) -> Result<ISO9660<T>> {
         let mut buf: [u8; 2048] = [0; 2048];
         let mut root = None;
         let mut primary = None;
+        let mut joliet = None;
+        let mut enhanced = None;
+        let mut boot_catalog_loc = None;
+        let mut partitions = Vec::new();
 
         // Skip the "system area"
-        let mut lba = 16;
+        let mut lba = session_start + 16;
 
-        // Read volume descriptors
+        // Read volume descriptors, which are addressed in 2048 byte sectors
+        // whatever the logical block size
         loop {
-            let count = reader.read_at(&mut buf, lba)?;
+            let count = file.reader().read_at(&mut buf, lba)?;
 
             if count != 2048 {
                 return Err(ISOError::ReadSize(2048, count));
@@ -67,11 +146,10 @@ impl<T: ISO9660Reader> ISO9660<T> {
                     root_directory_entry_identifier,
                     ..
                 }) => {
-                    if *logical_block_size != 2048 {
-                        // This is almost always the case, but technically
-                        // not guaranteed by the standard.
-                        // TODO: Implement this
-                        return Err(ISOError::InvalidFs("Block size not 2048"));
+                    // Almost always 2048, but the standard allows any
+                    // power of two from 512 up to the sector size.
+                    if ![512, 1024, 2048].contains(logical_block_size) {
+                        return Err(ISOError::InvalidFs("Invalid logical block size"));
                     }
 
                     root = Some((
@@ -80,6 +158,53 @@ impl<T: ISO9660Reader> ISO9660<T> {
                     ));
                     primary = descriptor;
                 }
+                Some(VolumeDescriptor::Supplementary {
+                    version: 2,
+                    file_structure_version: 2,
+                    root_directory_entry,
+                    root_directory_entry_identifier,
+                    ..
+                }) if enhanced.is_none() => {
+                    enhanced = Some((
+                        root_directory_entry.clone(),
+                        root_directory_entry_identifier.clone(),
+                    ));
+                }
+                Some(VolumeDescriptor::Supplementary {
+                    joliet_level: Some(joliet_level),
+                    root_directory_entry,
+                    root_directory_entry_identifier,
+                    ..
+                }) if joliet.is_none() => {
+                    joliet = Some((
+                        root_directory_entry.clone(),
+                        root_directory_entry_identifier.clone(),
+                        *joliet_level,
+                    ));
+                }
+                Some(VolumeDescriptor::BootRecord {
+                    boot_system_identifier,
+                    data,
+                    ..
+                }) if boot_system_identifier.trim_end_matches('\0') == EL_TORITO_IDENTIFIER => {
+                    boot_catalog_loc =
+                        Some(u32::from_le_bytes([data[0], data[1], data[2], data[3]]));
+                }
+                Some(VolumeDescriptor::VolumePartition {
+                    system_identifier,
+                    partition_identifier,
+                    location,
+                    size,
+                    system_use,
+                }) => {
+                    partitions.push(VolumePartition {
+                        system_identifier: system_identifier.clone(),
+                        identifier: partition_identifier.clone(),
+                        location: *location,
+                        size: *size,
+                        system_use: system_use.clone(),
+                    });
+                }
                 Some(VolumeDescriptor::VolumeDescriptorSetTerminator) => break,
                 _ => {}
             }
@@ -87,9 +212,6 @@ impl<T: ISO9660Reader> ISO9660<T> {
             lba += 1;
         }
 
-        let file = FileRef::new(reader);
-        let file2 = file.clone();
-
         let (root, primary) = match (root, primary) {
             (Some(root), Some(primary)) => (root, primary),
             _ => {
@@ -97,35 +219,247 @@ impl<T: ISO9660Reader> ISO9660<T> {
             }
         };
 
+        let block_size = match primary {
+            VolumeDescriptor::Primary {
+                logical_block_size, ..
+            } => logical_block_size,
+            _ => unreachable!(),
+        };
+        file.set_block_size(block_size);
+        file.set_root_loc(root.0.extent_loc);
+        let (_, system_use) = read_dot_entry(&file, root.0.extent_loc)?;
+        file.set_susp_skip(susp_skip(&system_use));
+        let root_rock_ridge = RockRidge::read(&system_use, &file)?;
+        let file2 = file.clone();
+
+        // Not needed for anything but faster lookups, so an invalid path
+        // table is ignored
+        let path_table = PathTable::read(
+            &file,
+            path_table_loc(&primary, PathTableType::L),
+            path_table_size(&primary),
+            PathTableType::L,
+        )
+        .ok();
+
+        let joliet_level = joliet.as_ref().map(|x| x.2);
+        let joliet_root = joliet.map(|(header, identifier, _)| {
+            let file = file.with_hierarchy(Hierarchy::Joliet, header.extent_loc);
+            ISODirectory::new(header, identifier, None, file)
+        });
+        let enhanced_root = enhanced.map(|(header, identifier)| {
+            let file = file.with_hierarchy(Hierarchy::Enhanced, header.extent_loc);
+            ISODirectory::new(header, identifier, None, file)
+        });
+
         Ok(ISO9660 {
-            _file: file,
-            root: ISODirectory::new(root.0, root.1, file2),
+            file,
+            root: ISODirectory::new(root.0, root.1, root_rock_ridge, file2),
+            joliet_root,
+            enhanced_root,
             primary,
+            joliet_level,
+            boot_catalog_loc,
+            path_table,
+            partitions,
+            session_start,
         })
     }
 
+    /// Open the last session of a multi-session image
+    pub fn new_last_session(mut reader: T) -> Result<ISO9660<T>> {
+        let sessions = ISO9660::find_sessions(&mut reader)?;
+        ISO9660::new_session(reader, sessions.last().copied().unwrap_or(0))
+    }
+
+    /// Find the start sector of each session of a multi-session image. Each
+    /// session appended by tools like growisofs or xorriso has its own
+    /// volume descriptor set, past the end of the volume recorded by the
+    /// previous one.
+    pub fn find_sessions(reader: &mut T) -> Result<Vec<u64>> {
+        let mut buf = vec![0; SESSION_SCAN_SECTORS * 2048];
+        let mut sessions = Vec::new();
+        let mut next = Some(0);
+
+        while let Some(session_start) = next.take() {
+            let end = match volume_end(reader, session_start)? {
+                Some(end) => end,
+                None => break,
+            };
+            sessions.push(session_start);
+
+            // Every sector is a candidate: on a CD, the lead-out and lead-in
+            // between sessions leave the next one anywhere
+            let mut candidate = end.max(session_start + 1);
+            'scan: loop {
+                let count = reader.read_at(&mut buf, candidate + 16)? / 2048;
+                for (i, sector) in buf.chunks_exact(2048).take(count).enumerate() {
+                    if sector.starts_with(b"\x01CD001") {
+                        next = Some(candidate + i as u64);
+                        break 'scan;
+                    }
+                }
+                if count < SESSION_SCAN_SECTORS {
+                    break;
+                }
+                candidate += count as u64;
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    /// Every session of the image, from the first to the last, each with
+    /// its own volume descriptors and hierarchy
+    pub fn sessions(&self) -> Result<Vec<ISO9660<T>>> {
+        let starts = ISO9660::find_sessions(&mut *self.file.reader())?;
+        starts
+            .into_iter()
+            .map(|start| ISO9660::open_session(self.file.shared(), start))
+            .collect()
+    }
+
+    /// Compare the hierarchy of this session with that of a later one,
+    /// returning the entries added, removed or modified, by path
+    pub fn diff(&self, newer: &ISO9660<T>) -> Result<Vec<Change>> {
+        session::diff(&self.root, &newer.root)
+    }
+
+    /// Start sector of the session this was opened from; 0 unless the image
+    /// has several sessions
+    pub fn session_start(&self) -> u64 {
+        self.session_start
+    }
+
+    /// Check the error detection and correction codes of every sector of
+    /// the volume, returning the damaged ones with the file or directory
+    /// they belong to. Only readers of raw Mode 1 and Mode 2 Form 1 sectors
+    /// have codes to check. Sectors are checked from the start of the
+    /// session, since a track of a disc image holds no earlier ones.
+    pub fn verify(&self) -> Result<Vec<BadSector>> {
+        let end = volume_end(&mut *self.file.reader(), self.session_start)?
+            .ok_or(ISOError::InvalidFs("No primary volume descriptor"))?;
+        verify::verify(&self.file, &self.root, self.session_start..end)
+    }
+
     pub fn open(&self, path: &str) -> Result<Option<DirectoryEntry<T>>> {
+        self.lookup(path, false)
+    }
+
+    /// Like `open`, but resolves Rock Ridge symbolic links, both in the
+    /// middle of the path and at the end of it.
+    pub fn open_follow_symlinks(&self, path: &str) -> Result<Option<DirectoryEntry<T>>> {
+        self.lookup(path, true)
+    }
+
+    fn lookup(&self, path: &str, follow_symlinks: bool) -> Result<Option<DirectoryEntry<T>>> {
+        // Remaining path segments, in reverse order
+        let mut segments = path_segments(path);
+        let mut symlinks = 0;
+
         // TODO: avoid clone()
-        let mut entry = DirectoryEntry::Directory(self.root.clone());
-        for segment in path.split('/').filter(|x| !x.is_empty()) {
-            let parent = match entry {
-                DirectoryEntry::Directory(dir) => dir,
-                _ => return Ok(None),
-            };
+        let mut parent = self.root.clone();
 
-            entry = match parent.find(segment)? {
+        // Resolve leading directories through the path table, rather than
+        // reading each of them. It only has ISO 9660 identifiers, which
+        // Rock Ridge names replace.
+        let path_table = self.path_table.as_ref();
+        if let Some(path_table) = path_table.filter(|_| self.file.susp_skip().is_none()) {
+            let mut index = 1;
+            while segments.len() > 1 {
+                match path_table.find(index, segments.last().unwrap()) {
+                    Some(child) => index = child,
+                    None => break,
+                }
+                segments.pop();
+            }
+
+            if index != 1 {
+                let dir = path_table.get(index).unwrap();
+                let loc = dir.extent_loc + dir.extended_attribute_record_length as u32;
+                let (header, _) = read_dot_entry(&self.file, loc)?;
+                let identifier = dir.identifier.clone();
+                parent = ISODirectory::new(header, identifier, None, self.file.clone());
+            }
+        }
+
+        let mut entry = DirectoryEntry::Directory(parent.clone());
+        while let Some(segment) = segments.pop() {
+            entry = match parent.find(&segment)? {
                 Some(entry) => entry,
                 None => return Ok(None),
             };
+
+            match entry {
+                DirectoryEntry::Symlink(ref link) if follow_symlinks => {
+                    symlinks += 1;
+                    if symlinks > MAX_SYMLINKS {
+                        return Err(ISOError::InvalidFs("Too many levels of symbolic links"));
+                    }
+
+                    // Relative targets are resolved from the directory
+                    // containing the link
+                    if link.target().starts_with('/') {
+                        parent = self.root.clone();
+                    }
+                    segments.extend(path_segments(link.target()));
+                    entry = DirectoryEntry::Directory(parent.clone());
+                }
+                DirectoryEntry::Directory(ref dir) => parent = dir.clone(),
+                _ if !segments.is_empty() => return Ok(None),
+                _ => {}
+            }
         }
 
         Ok(Some(entry))
     }
 
+    /// Joliet level (1 to 3) of the Joliet hierarchy, if present
+    pub fn joliet_level(&self) -> Option<u8> {
+        self.joliet_level
+    }
+
+    /// Parse the El Torito boot catalog, if the image is bootable
+    pub fn boot_catalog(&self) -> Result<Option<BootCatalog>> {
+        match self.boot_catalog_loc {
+            Some(loc) => Ok(Some(BootCatalog::read(&self.file, loc)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the boot image of an entry of the boot catalog
+    pub fn boot_image(&self, entry: &BootEntry) -> ISOFileReader<T> {
+        let lba = entry.load_rba as u64 * self.file.blocks_per_sector();
+        ISOFileReader::new(self.file.clone(), vec![(lba, entry.image_size())])
+    }
+
+    /// Volume partitions described by volume partition descriptors
+    pub fn partitions(&self) -> &[VolumePartition] {
+        &self.partitions
+    }
+
+    /// Read the logical blocks of a volume partition
+    pub fn read_partition(&self, partition: &VolumePartition) -> ISOFileReader<T> {
+        let size = partition.size as u64 * self.file.block_size() as u64;
+        ISOFileReader::new(self.file.clone(), vec![(partition.location as u64, size)])
+    }
+
+    /// Read the path table of the primary hierarchy, in either byte order
+    pub fn path_table(&self, table_type: PathTableType) -> Result<PathTable> {
+        PathTable::read(
+            &self.file,
+            path_table_loc(&self.primary, table_type),
+            path_table_size(&self.primary),
+            table_type,
+        )
+    }
+
+    /// Logical block size of the volume; 512, 1024 or 2048
     pub fn block_size(&self) -> u16 {
-        2048 // XXX
+        self.file.block_size()
     }
 
+    primary_prop_str!(volume_identifier);
     primary_prop_str!(volume_set_identifier);
     primary_prop_str!(publisher_identifier);
     primary_prop_str!(data_preparer_identifier);
@@ -133,4 +467,92 @@ impl<T: ISO9660Reader> ISO9660<T> {
     primary_prop_str!(copyright_file_identifier);
     primary_prop_str!(abstract_file_identifier);
     primary_prop_str!(bibliographic_file_identifier);
+
+    primary_prop_time!(creation_time);
+    primary_prop_time!(modification_time);
+}
+
+/// The sector after the end of the volume of the session starting at
+/// `session_start`, or `None` if there is no valid session there
+fn volume_end<T: ISO9660Reader>(reader: &mut T, session_start: u64) -> Result<Option<u64>> {
+    let mut buf = [0; 2048];
+    let mut lba = session_start + 16;
+    while reader.read_at(&mut buf, lba)? == 2048 {
+        match VolumeDescriptor::parse(&buf) {
+            Ok(Some(VolumeDescriptor::Primary {
+                volume_space_size,
+                logical_block_size,
+                ..
+            })) => {
+                let size = volume_space_size as u64 * logical_block_size as u64;
+                return Ok(Some(size.div_ceil(2048)));
+            }
+            Ok(Some(VolumeDescriptor::VolumeDescriptorSetTerminator)) | Err(_) => break,
+            Ok(_) => lba += 1,
+        }
+    }
+    Ok(None)
+}
+
+fn path_table_loc(primary: &VolumeDescriptor, table_type: PathTableType) -> u32 {
+    match (primary, table_type) {
+        (VolumeDescriptor::Primary { path_table_loc, .. }, PathTableType::L) => *path_table_loc,
+        (
+            VolumeDescriptor::Primary {
+                path_table_loc_be, ..
+            },
+            PathTableType::M,
+        ) => *path_table_loc_be,
+        _ => unreachable!(),
+    }
+}
+
+fn path_table_size(primary: &VolumeDescriptor) -> u32 {
+    match primary {
+        VolumeDescriptor::Primary {
+            path_table_size, ..
+        } => *path_table_size,
+        _ => unreachable!(),
+    }
+}
+
+impl<R: Read + Seek> ISO9660<OffsetReader<R>> {
+    /// Open a filesystem starting at a byte offset inside a larger image,
+    /// as found by `probe`
+    pub fn new_at_offset(reader: R, offset: u64) -> Result<ISO9660<OffsetReader<R>>> {
+        ISO9660::new(OffsetReader::new(reader, offset))
+    }
+
+    /// Find the byte offsets of ISO 9660 filesystems inside a disk image,
+    /// including inside MBR and GPT partitions
+    pub fn probe(reader: &mut R) -> Result<Vec<u64>> {
+        Ok(offset::probe(reader)?)
+    }
+}
+
+impl<R: Read + Seek> ISO9660<TrackReader<R>> {
+    /// Open the filesystem of a data track, whose volume descriptors are at
+    /// the start of the track even if it doesn't start the disc
+    pub fn new_track(reader: TrackReader<R>) -> Result<ISO9660<TrackReader<R>>> {
+        let start = reader.start();
+        ISO9660::new_session(reader, start)
+    }
+}
+
+fn path_segments(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|x| !x.is_empty())
+        .rev()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check for the SUSP "SP" entry, which must be at the start of the system
+/// use area of the root directory's '.' entry.
+fn susp_skip(system_use: &[u8]) -> Option<u8> {
+    // Without SUSP, the system use area may contain anything
+    match susp_entries(system_use).first() {
+        Some(SuspEntry::SharingProtocol { skip }) => Some(*skip),
+        _ => None,
+    }
 }
@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+//! Alcohol 120% MDS descriptors, which describe the sessions and tracks of
+//! the sectors stored in an MDF file
+
+use crate::{ISOError, Result, Track, TrackMode};
+
+const SIGNATURE: &[u8] = b"MEDIA DESCRIPTOR";
+const HEADER_SIZE: usize = 88;
+const SESSION_SIZE: usize = 24;
+const TRACK_SIZE: usize = 80;
+
+/// The tracks of an MDS descriptor, and the name of the MDF file they are
+/// stored in, if it is not named after the descriptor
+pub(crate) fn read_tracks(mds: &[u8]) -> Result<(Vec<Track>, Option<String>)> {
+    if mds.len() < HEADER_SIZE || !mds.starts_with(SIGNATURE) {
+        return Err(ISOError::InvalidFs("Not an MDS descriptor"));
+    }
+    // DVD descriptors have a different layout
+    if u16_le(&mds[18..]) >= 0x10 {
+        return Err(ISOError::InvalidFs("Unsupported MDS medium type"));
+    }
+    let session_count = u16_le(&mds[20..]) as usize;
+    let sessions_offset = u32_le(&mds[80..]) as usize;
+
+    let mut tracks = Vec::new();
+    let mut file_name = None;
+    for i in 0..session_count {
+        let session = block(mds, sessions_offset + i * SESSION_SIZE, SESSION_SIZE)?;
+        let session_number = u16_le(&session[8..]) as u8;
+        let block_count = session[10] as usize;
+        let tracks_offset = u32_le(&session[20..]) as usize;
+
+        let mut first_in_session = true;
+        for j in 0..block_count {
+            let entry = block(mds, tracks_offset + j * TRACK_SIZE, TRACK_SIZE)?;
+            // Points over 99 describe the lead-in, not tracks
+            let number = entry[4];
+            if number == 0 || number > 99 {
+                continue;
+            }
+
+            let sector_size = u16_le(&entry[16..]);
+            let mode = match entry[0] & 0x0f {
+                0x9 if sector_size == 2448 => TrackMode::Cdg,
+                0x9 => TrackMode::Audio,
+                0xa => TrackMode::Mode1(sector_size),
+                0xb..=0xd => TrackMode::Mode2(sector_size),
+                _ => return Err(ISOError::InvalidFs("Unknown MDS track mode")),
+            };
+
+            // The extra block holds the pregap and length of the track
+            let extra_offset = u32_le(&entry[12..]) as usize;
+            let (pregap, length) = if extra_offset != 0 {
+                let extra = block(mds, extra_offset, 8)?;
+                (u32_le(extra) as u64, u32_le(&extra[4..]) as u64)
+            } else {
+                (0, 0)
+            };
+            // The pregap of the first track of a session is not stored
+            let stored_pregap = if first_in_session { 0 } else { pregap };
+            first_in_session = false;
+
+            if file_name.is_none() && u32_le(&entry[48..]) != 0 {
+                file_name = read_file_name(mds, u32_le(&entry[52..]) as usize)?;
+            }
+
+            let start_offset = u32_le(&entry[40..]) as u64 | (u32_le(&entry[44..]) as u64) << 32;
+            tracks.push(Track {
+                number,
+                session: session_number,
+                mode,
+                file: 0,
+                offset: start_offset + stored_pregap * mode.sector_size() as u64,
+                start: u32_le(&entry[36..]) as u64,
+                pregap,
+                stored_pregap,
+                length,
+            });
+        }
+    }
+
+    if tracks.is_empty() {
+        return Err(ISOError::InvalidFs("No tracks in MDS descriptor"));
+    }
+    Ok((tracks, file_name))
+}
+
+/// The name of the MDF file from a footer block. `*.mdf` stands for the
+/// name of the descriptor.
+fn read_file_name(mds: &[u8], footer_offset: usize) -> Result<Option<String>> {
+    let footer = block(mds, footer_offset, 16)?;
+    let name_offset = u32_le(footer) as usize;
+    let wide = u32_le(&footer[4..]) != 0;
+    let name = mds
+        .get(name_offset..)
+        .ok_or(ISOError::InvalidFs("Truncated MDS descriptor"))?;
+
+    let name = if wide {
+        let units = name
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .take_while(|&x| x != 0)
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    } else {
+        let len = name.iter().position(|&x| x == 0).unwrap_or(name.len());
+        String::from_utf8_lossy(&name[..len]).into_owned()
+    };
+    Ok(if name.starts_with('*') {
+        None
+    } else {
+        Some(name)
+    })
+}
+
+fn block(mds: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    mds.get(offset..offset + len)
+        .ok_or(ISOError::InvalidFs("Truncated MDS descriptor"))
+}
+
+fn u16_le(data: &[u8]) -> u16 {
+    u16::from_le_bytes([data[0], data[1]])
+}
+
+fn u32_le(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+//! Nero NRG images, which store the tracks first and describe them in a
+//! list of chunks at the end of the file, found from a footer
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{ISOError, Result, Track, TrackMode};
+
+// CUES times count the 2 second pregap before the first track
+const LEAD_IN: i64 = 150;
+// Limit on the size of the chunk list, which is read into memory
+const MAX_CHUNKS_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Where each track of a session is stored
+struct StoredTrack {
+    mode: TrackMode,
+    pregap_offset: u64,
+    start_offset: u64,
+    end_offset: u64,
+    /// Start sector, for images written track at once, which have no cue
+    start: Option<i64>,
+}
+
+/// Read the tracks of an NRG image, of version 1 (`NERO` footer, 32-bit
+/// offsets) or 2 (`NER5` footer, 64-bit offsets)
+pub(crate) fn read_tracks<T: Read + Seek>(reader: &mut T) -> Result<Vec<Track>> {
+    let size = reader.seek(SeekFrom::End(0))?;
+    let mut footer = [0; 12];
+    if size < footer.len() as u64 {
+        return Err(ISOError::InvalidFs("No NRG footer"));
+    }
+    reader.seek(SeekFrom::Start(size - 12))?;
+    reader.read_exact(&mut footer)?;
+    let chunks_offset = if &footer[..4] == b"NER5" {
+        u64_be(&footer[4..])
+    } else if &footer[4..8] == b"NERO" {
+        u32_be(&footer[8..]) as u64
+    } else {
+        return Err(ISOError::InvalidFs("No NRG footer"));
+    };
+    if chunks_offset >= size || size - chunks_offset > MAX_CHUNKS_SIZE {
+        return Err(ISOError::InvalidFs("Invalid NRG chunk offset"));
+    }
+
+    let mut chunks = vec![0; (size - chunks_offset) as usize];
+    reader.seek(SeekFrom::Start(chunks_offset))?;
+    reader.read_exact(&mut chunks)?;
+
+    let mut tracks = Vec::new();
+    let mut session = 0;
+    // Start sector of each index 1, by track number, from the cue chunks
+    let mut starts = HashMap::new();
+    let mut pos = 0;
+    while pos + 8 <= chunks.len() {
+        let id = &chunks[pos..pos + 4];
+        let len = u32_be(&chunks[pos + 4..]) as usize;
+        if id == b"END!" {
+            break;
+        }
+        let data = chunks
+            .get(pos + 8..pos + 8 + len)
+            .ok_or(ISOError::InvalidFs("Truncated NRG chunk"))?;
+
+        match id {
+            b"CUEX" | b"CUES" => {
+                for entry in data.chunks_exact(8) {
+                    let (track, index) = (bcd(entry[1]), bcd(entry[2]));
+                    if index != 1 {
+                        continue;
+                    }
+                    // CUEX has sectors relative to the end of the first
+                    // pregap; CUES has minutes, seconds and frames from the
+                    // start of the disc
+                    let start = if id == b"CUEX" {
+                        u32_be(&entry[4..]) as i32 as i64
+                    } else {
+                        (entry[5] as i64 * 60 + entry[6] as i64) * 75 + entry[7] as i64 - LEAD_IN
+                    };
+                    starts.insert(track, start);
+                }
+            }
+            b"DAOX" | b"DAOI" => {
+                session += 1;
+                let (entry_len, offset_len) = if id == b"DAOX" { (42, 8) } else { (30, 4) };
+                if data.len() < 22 {
+                    return Err(ISOError::InvalidFs("Truncated NRG DAO chunk"));
+                }
+                let first_track = data[20];
+                for (i, entry) in data[22..].chunks_exact(entry_len).enumerate() {
+                    let sector_size = u16::from_be_bytes([entry[12], entry[13]]);
+                    let offset = |n: usize| {
+                        let start = 18 + n * offset_len;
+                        if offset_len == 8 {
+                            u64_be(&entry[start..])
+                        } else {
+                            u32_be(&entry[start..]) as u64
+                        }
+                    };
+                    let track = StoredTrack {
+                        mode: track_mode(entry[14], sector_size)?,
+                        pregap_offset: offset(0),
+                        start_offset: offset(1),
+                        end_offset: offset(2),
+                        start: None,
+                    };
+                    let number = track_number(first_track as usize + i)?;
+                    tracks.push((number, session, track));
+                }
+            }
+            b"ETN2" | b"ETNF" => {
+                session += 1;
+                let entry_len = if id == b"ETN2" { 32 } else { 20 };
+                for entry in data.chunks_exact(entry_len) {
+                    let (offset, length, rest) = if id == b"ETN2" {
+                        (u64_be(entry), u64_be(&entry[8..]), &entry[16..])
+                    } else {
+                        (
+                            u32_be(entry) as u64,
+                            u32_be(&entry[4..]) as u64,
+                            &entry[8..],
+                        )
+                    };
+                    let mode = track_mode(u32_be(rest) as u8, 0)?;
+                    let track = StoredTrack {
+                        mode,
+                        pregap_offset: offset,
+                        start_offset: offset,
+                        end_offset: offset + length,
+                        start: Some(u32_be(&rest[4..]) as i64),
+                    };
+                    tracks.push((track_number(tracks.len() + 1)?, session, track));
+                }
+            }
+            _ => {}
+        }
+
+        pos += 8 + len;
+    }
+
+    if tracks.is_empty() {
+        return Err(ISOError::InvalidFs("No tracks in NRG image"));
+    }
+
+    let mut next_start = 0;
+    tracks
+        .into_iter()
+        .map(|(number, session, track)| {
+            let sector_size = track.mode.sector_size() as u64;
+            let stored_pregap =
+                track.start_offset.saturating_sub(track.pregap_offset) / sector_size;
+            let start = track
+                .start
+                .or_else(|| starts.get(&number).copied())
+                .unwrap_or(next_start + stored_pregap as i64);
+            if start < 0 {
+                return Err(ISOError::InvalidFs("Invalid NRG track start"));
+            }
+            let length = track.end_offset.saturating_sub(track.start_offset) / sector_size;
+            next_start = start + length as i64;
+            Ok(Track {
+                number,
+                session,
+                mode: track.mode,
+                file: 0,
+                offset: track.start_offset,
+                start: start as u64,
+                pregap: stored_pregap,
+                stored_pregap,
+                length,
+            })
+        })
+        .collect()
+}
+
+/// The mode of a track from its NRG mode code. The sector size is recorded
+/// for images written disc at once, and implied by the mode otherwise.
+fn track_mode(code: u8, sector_size: u16) -> Result<TrackMode> {
+    let mode = match code {
+        0x00 => TrackMode::Mode1(2048),
+        0x02 => TrackMode::Mode2(2048),
+        0x03 => TrackMode::Mode2(2336),
+        0x05 => TrackMode::Mode1(2352),
+        0x06 => TrackMode::Mode2(2352),
+        0x07 => TrackMode::Audio,
+        0x0f => TrackMode::Mode1(2448),
+        0x10 => TrackMode::Cdg,
+        0x11 => TrackMode::Mode2(2448),
+        _ => return Err(ISOError::InvalidFs("Unknown NRG track mode")),
+    };
+    Ok(match mode {
+        TrackMode::Mode1(_) if sector_size != 0 => TrackMode::Mode1(sector_size),
+        TrackMode::Mode2(_) if sector_size != 0 => TrackMode::Mode2(sector_size),
+        mode => mode,
+    })
+}
+
+/// A track number, which must be between 1 and 99
+fn track_number(number: usize) -> Result<u8> {
+    match number {
+        1..=99 => Ok(number as u8),
+        _ => Err(ISOError::InvalidFs("Invalid NRG track number")),
+    }
+}
+
+fn bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0xf)
+}
+
+fn u32_be(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn u64_be(data: &[u8]) -> u64 {
+    (u32_be(data) as u64) << 32 | u32_be(&data[4..]) as u64
+}
@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+// The first volume descriptor is at sector 16 of the filesystem
+const DESCRIPTOR_OFFSET: u64 = 16 * 2048;
+// Signature of a primary volume descriptor
+const PRIMARY_SIGNATURE: &[u8] = b"\x01CD001";
+// How far into the image `probe` looks for a filesystem outside partitions
+const SCAN_LIMIT: u64 = 64 * 1024 * 1024;
+const SCAN_CHUNK: usize = 1024 * 1024;
+// Largest GPT partition entry read; the table has at most 1024 entries
+const MAX_GPT_ENTRY_SIZE: usize = 4096;
+
+/// A reader for a filesystem starting at a byte offset inside a larger
+/// image, like a partition of a whole disk image
+#[derive(Debug)]
+pub struct OffsetReader<T: Read + Seek> {
+    inner: T,
+    offset: u64,
+}
+
+impl<T: Read + Seek> OffsetReader<T> {
+    pub fn new(inner: T, offset: u64) -> OffsetReader<T> {
+        OffsetReader { inner, offset }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read + Seek> Read for OffsetReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<T: Read + Seek> Seek for OffsetReader<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => self.inner.seek(SeekFrom::Start(self.offset + pos))?,
+            pos => self.inner.seek(pos)?,
+        };
+        pos.checked_sub(self.offset)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek"))
+    }
+}
+
+/// Find the byte offsets at which ISO 9660 filesystems start in a disk
+/// image: at the start of the image, at the start of MBR and GPT
+/// partitions, and at any 512 byte sector boundary near the start of the
+/// image.
+pub(crate) fn probe<T: Read + Seek>(reader: &mut T) -> io::Result<Vec<u64>> {
+    let mut candidates = vec![0];
+    candidates.extend(mbr_partitions(reader)?);
+    candidates.extend(gpt_partitions(reader)?);
+
+    let mut offsets = Vec::new();
+    for offset in candidates {
+        if has_primary_descriptor(reader, offset)? {
+            offsets.push(offset);
+        }
+    }
+
+    // Look for primary volume descriptors on sector boundaries
+    let mut buf = vec![0; SCAN_CHUNK + PRIMARY_SIGNATURE.len()];
+    let mut pos = DESCRIPTOR_OFFSET;
+    while pos < SCAN_LIMIT + DESCRIPTOR_OFFSET {
+        reader.seek(SeekFrom::Start(pos))?;
+        let count = read_full(reader, &mut buf)?;
+        for i in (0..count.min(SCAN_CHUNK)).step_by(512) {
+            if buf[i..count].starts_with(PRIMARY_SIGNATURE) {
+                offsets.push(pos + i as u64 - DESCRIPTOR_OFFSET);
+            }
+        }
+        if count < buf.len() {
+            break;
+        }
+        pos += SCAN_CHUNK as u64;
+    }
+
+    offsets.sort_unstable();
+    offsets.dedup();
+    Ok(offsets)
+}
+
+fn has_primary_descriptor<T: Read + Seek>(reader: &mut T, offset: u64) -> io::Result<bool> {
+    let mut buf = [0; 6];
+    reader.seek(SeekFrom::Start(offset + DESCRIPTOR_OFFSET))?;
+    Ok(read_full(reader, &mut buf)? == buf.len() && buf == PRIMARY_SIGNATURE)
+}
+
+/// Byte offsets of the partitions of the MBR partition table, if any
+fn mbr_partitions<T: Read + Seek>(reader: &mut T) -> io::Result<Vec<u64>> {
+    let mut mbr = [0; 512];
+    reader.seek(SeekFrom::Start(0))?;
+    if read_full(reader, &mut mbr)? != mbr.len() || mbr[510..] != [0x55, 0xaa] {
+        return Ok(Vec::new());
+    }
+
+    Ok(mbr[446..510]
+        .chunks_exact(16)
+        // Skip empty entries, and the protective entry of GPT
+        .filter(|x| x[4] != 0 && x[4] != 0xee)
+        .map(|x| u32::from_le_bytes([x[8], x[9], x[10], x[11]]) as u64 * 512)
+        .filter(|&x| x != 0)
+        .collect())
+}
+
+/// Byte offsets of the partitions of the GPT partition table, if any. Only
+/// 512 byte logical sectors are supported.
+fn gpt_partitions<T: Read + Seek>(reader: &mut T) -> io::Result<Vec<u64>> {
+    let mut header = [0; 92];
+    reader.seek(SeekFrom::Start(512))?;
+    if read_full(reader, &mut header)? != header.len() || &header[..8] != b"EFI PART" {
+        return Ok(Vec::new());
+    }
+
+    let u32_at =
+        |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+    let entries_lba = u32_at(72) as u64 | (u32_at(76) as u64) << 32;
+    let entry_count = u32_at(80).min(1024) as usize;
+    let entry_size = u32_at(84) as usize;
+    // UEFI requires entries of 128 times a power of two bytes; anything
+    // else would have the table allocated at whatever size the header says
+    if !(128..=MAX_GPT_ENTRY_SIZE).contains(&entry_size) || !entry_size.is_power_of_two() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = vec![0; entry_count * entry_size];
+    reader.seek(SeekFrom::Start(entries_lba * 512))?;
+    let count = read_full(reader, &mut entries)?;
+
+    Ok(entries[..count]
+        .chunks_exact(entry_size)
+        // Unused entries have a zero type GUID
+        .filter(|x| x[..16].iter().any(|&x| x != 0))
+        .map(|x| {
+            let mut lba = [0; 8];
+            lba.copy_from_slice(&x[32..40]);
+            u64::from_le_bytes(lba) * 512
+        })
+        .collect())
+}
+
+// Like `read_exact`, but returns how much was read before the end of file
+fn read_full<T: Read>(reader: &mut T, mut buf: &mut [u8]) -> io::Result<usize> {
+    let len = buf.len();
+    while !buf.is_empty() {
+        match reader.read(buf) {
+            Ok(0) => break,
+            Ok(count) => buf = &mut buf[count..],
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len - buf.len())
+}
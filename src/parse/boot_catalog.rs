@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use nom::bytes::complete::{tag, take};
+use nom::combinator::map;
+use nom::number::complete::*;
+use nom::IResult;
+
+use crate::eltorito::{BootEntry, Emulation, Platform};
+
+fn take_id_string(count: usize) -> impl Fn(&[u8]) -> IResult<&[u8], String> {
+    move |i: &[u8]| {
+        map(take(count), |x: &[u8]| {
+            String::from_utf8_lossy(x)
+                .trim_end_matches(&[' ', '\0'][..])
+                .to_string()
+        })(i)
+    }
+}
+
+/// Validation entry: platform of the default entry and ID string of the
+/// manufacturer. The checksum is verified by the caller.
+pub(crate) fn validation_entry(i: &[u8]) -> IResult<&[u8], (Platform, String)> {
+    let (i, _) = tag([1])(i)?; // header ID
+    let (i, platform) = map(le_u8, Platform::from)(i)?;
+    let (i, _) = take(2usize)(i)?; // reserved
+    let (i, manufacturer) = take_id_string(24)(i)?;
+    let (i, _) = le_u16(i)?; // checksum
+    let (i, _) = tag([0x55, 0xaa])(i)?;
+    Ok((i, (platform, manufacturer)))
+}
+
+/// Initial/default entry, or section entry. Both share a layout, but the
+/// selection criteria are only meaningful in the latter.
+pub(crate) fn boot_entry(i: &[u8]) -> IResult<&[u8], BootEntry> {
+    let (i, boot_indicator) = le_u8(i)?;
+    let (i, media_type) = le_u8(i)?;
+    let (i, load_segment) = le_u16(i)?;
+    let (i, system_type) = le_u8(i)?;
+    let (i, _) = le_u8(i)?; // unused
+    let (i, sector_count) = le_u16(i)?;
+    let (i, load_rba) = le_u32(i)?;
+    let (i, _) = take(20usize)(i)?; // selection criteria, or unused
+    Ok((
+        i,
+        BootEntry {
+            bootable: boot_indicator == 0x88,
+            emulation: Emulation::from(media_type & 0x0f),
+            load_segment,
+            system_type,
+            sector_count,
+            load_rba,
+        },
+    ))
+}
+
+/// Section header: whether it is the final one, the platform, the number of
+/// section entries following it, and its ID string.
+pub(crate) fn section_header(i: &[u8]) -> IResult<&[u8], (bool, Platform, u16, String)> {
+    let (i, indicator) = le_u8(i)?;
+    let (i, platform) = map(le_u8, Platform::from)(i)?;
+    let (i, entry_count) = le_u16(i)?;
+    let (i, identifier) = take_id_string(28)(i)?;
+    Ok((i, (indicator == 0x91, platform, entry_count, identifier)))
+}
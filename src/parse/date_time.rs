@@ -2,7 +2,7 @@
 
 use nom::bytes::complete::take;
 use nom::combinator::map_res;
-use nom::number::complete::le_u8;
+use nom::number::complete::{le_i8, le_u8};
 use nom::sequence::tuple;
 use nom::IResult;
 use std::convert::TryFrom;
@@ -11,7 +11,7 @@ use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
 
 pub fn date_time(i: &[u8]) -> IResult<&[u8], OffsetDateTime> {
     let (i, (year, month, day, hour, minute, second, gmt_offset)) =
-        tuple((le_u8, le_u8, le_u8, le_u8, le_u8, le_u8, le_u8))(i)?;
+        tuple((le_u8, le_u8, le_u8, le_u8, le_u8, le_u8, le_i8))(i)?;
 
     // Create Date and Time from parsed values. Since those values can be 0,
     // creating Date and Time struct can fail, in this case assume default
@@ -47,11 +47,12 @@ pub fn date_time_ascii(i: &[u8]) -> IResult<&[u8], OffsetDateTime> {
             ascii_i32(2),
             ascii_i32(2),
             ascii_i32(2),
-            le_u8,
+            le_i8,
         ))(i)?;
 
+    // Unlike the binary format, the year is not an offset from 1900
     let date = Date::from_calendar_date(
-        1900 + tm_year,
+        tm_year,
         time::Month::try_from(tm_mon as u8).unwrap_or(time::Month::January),
         tm_mday as u8,
     )
@@ -70,3 +71,13 @@ pub fn date_time_ascii(i: &[u8]) -> IResult<&[u8], OffsetDateTime> {
 
     Ok((i, PrimitiveDateTime::new(date, time).assume_offset(offset)))
 }
+
+/// Like `date_time_ascii`, but `None` for the all zero value that means the
+/// date is not specified
+pub fn optional_date_time_ascii(i: &[u8]) -> IResult<&[u8], Option<OffsetDateTime>> {
+    let unspecified = i
+        .get(..17)
+        .is_some_and(|x| x[..16].iter().all(|&x| x == b'0'));
+    let (i, date_time) = date_time_ascii(i)?;
+    Ok((i, if unspecified { None } else { Some(date_time) }))
+}
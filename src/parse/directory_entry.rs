@@ -5,11 +5,10 @@ use time::OffsetDateTime;
 use super::both_endian::{both_endian16, both_endian32};
 use super::date_time::date_time;
 use crate::Result;
-use nom::combinator::{map, map_res};
+use nom::bytes::complete::{tag, take};
 use nom::multi::length_data;
-use nom::number::complete::le_u8;
+use nom::number::complete::{be_u16, le_u8};
 use nom::IResult;
-use std::str;
 
 bitflags! {
     #[derive(Clone, Debug)]
@@ -24,6 +23,40 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Attributes of a file on a CD-ROM XA disc
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct XaAttributes: u16 {
+        const OWNER_READ = 1 << 0;
+        const OWNER_EXECUTE = 1 << 2;
+        const GROUP_READ = 1 << 4;
+        const GROUP_EXECUTE = 1 << 6;
+        const WORLD_READ = 1 << 8;
+        const WORLD_EXECUTE = 1 << 10;
+        /// Stored in Mode 2 Form 1 sectors
+        const MODE2 = 1 << 11;
+        /// Stored in Mode 2 Form 2 sectors, which hold 2324 bytes of data
+        const MODE2_FORM2 = 1 << 12;
+        /// Sectors of several streams are interleaved, like video and audio
+        const INTERLEAVED = 1 << 13;
+        /// A CD-DA audio track
+        const CDDA = 1 << 14;
+        const DIRECTORY = 1 << 15;
+    }
+}
+
+/// The CD-ROM XA record at the start of the system use area of directory
+/// records of XA discs, like those of the PlayStation or Video CDs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XaRecord {
+    pub group_id: u16,
+    pub user_id: u16,
+    pub attributes: XaAttributes,
+    /// File number in the subheader of the sectors of the file, which
+    /// tells interleaved files apart
+    pub file_number: u8,
+}
+
 #[derive(Clone, Debug)]
 pub struct DirectoryEntryHeader {
     pub length: u8,
@@ -35,15 +68,20 @@ pub struct DirectoryEntryHeader {
     pub file_unit_size: u8,
     pub interleave_gap_size: u8,
     pub volume_sequence_number: u16,
+    /// CD-ROM XA record from the system use area, if any
+    pub xa: Option<XaRecord>,
 }
 
+/// A parsed header, with the raw identifier and system use area
+pub type DirectoryRecord<'a> = (DirectoryEntryHeader, &'a [u8], &'a [u8]);
+
 impl DirectoryEntryHeader {
-    pub fn parse(input: &[u8]) -> Result<(DirectoryEntryHeader, String)> {
+    pub fn parse(input: &[u8]) -> Result<DirectoryRecord<'_>> {
         Ok(directory_entry(input)?.1)
     }
 }
 
-pub fn directory_entry(i: &[u8]) -> IResult<&[u8], (DirectoryEntryHeader, String)> {
+pub fn directory_entry(i: &[u8]) -> IResult<&[u8], DirectoryRecord<'_>> {
     let (i, length) = le_u8(i)?;
     let (i, extended_attribute_record_length) = le_u8(i)?;
     let (i, extent_loc) = both_endian32(i)?;
@@ -53,9 +91,16 @@ pub fn directory_entry(i: &[u8]) -> IResult<&[u8], (DirectoryEntryHeader, String
     let (i, file_unit_size) = le_u8(i)?;
     let (i, interleave_gap_size) = le_u8(i)?;
     let (i, volume_sequence_number) = both_endian16(i)?;
-    let (i, identifier) = map(map_res(length_data(le_u8), str::from_utf8), str::to_string)(i)?;
+    // The encoding of the identifier depends on the hierarchy (Joliet uses
+    // UCS-2), so it is left to the caller to decode.
+    let (i, identifier) = length_data(le_u8)(i)?;
+    // The identifier is padded to an even length
+    let header_length = 33 + identifier.len();
+    let (i, _) = take(header_length % 2)(i)?;
     // After the file identifier, ISO 9660 allows addition space for
-    // system use. Ignore that for now.
+    // system use, which extensions like Rock Ridge make use of.
+    let system_use_length = (length as usize).saturating_sub(header_length + header_length % 2);
+    let (i, system_use) = take(system_use_length)(i)?;
 
     Ok((
         i,
@@ -70,8 +115,28 @@ pub fn directory_entry(i: &[u8]) -> IResult<&[u8], (DirectoryEntryHeader, String
                 file_unit_size,
                 interleave_gap_size,
                 volume_sequence_number,
+                xa: xa_record(system_use).ok().map(|x| x.1),
             },
             identifier,
+            system_use,
         ),
     ))
 }
+
+fn xa_record(i: &[u8]) -> IResult<&[u8], XaRecord> {
+    let (i, group_id) = be_u16(i)?;
+    let (i, user_id) = be_u16(i)?;
+    let (i, attributes) = be_u16(i)?;
+    let (i, _) = tag(b"XA")(i)?;
+    let (i, file_number) = le_u8(i)?;
+    let (i, _) = take(5usize)(i)?;
+    Ok((
+        i,
+        XaRecord {
+            group_id,
+            user_id,
+            attributes: XaAttributes::from_bits_truncate(attributes),
+            file_number,
+        },
+    ))
+}
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use nom::bytes::complete::take;
+use nom::number::complete::*;
+use nom::IResult;
+
+use super::both_endian::both_endian16;
+use super::date_time::optional_date_time_ascii;
+use crate::directory_entry::{ExtendedAttributes, Timestamps};
+
+pub(crate) fn extended_attribute_record(i: &[u8]) -> IResult<&[u8], ExtendedAttributes> {
+    let (i, owner) = both_endian16(i)?;
+    let (i, group) = both_endian16(i)?;
+    let (i, permissions) = be_u16(i)?;
+    let (i, creation) = optional_date_time_ascii(i)?;
+    let (i, modify) = optional_date_time_ascii(i)?;
+    let (i, expiration) = optional_date_time_ascii(i)?;
+    let (i, effective) = optional_date_time_ascii(i)?;
+    let (i, record_format) = le_u8(i)?;
+    let (i, record_attributes) = le_u8(i)?;
+    let (i, record_length) = both_endian16(i)?;
+    let (i, system_identifier) = take(32usize)(i)?;
+    let (i, system_use) = take(64usize)(i)?;
+    let (i, version) = le_u8(i)?;
+    let (i, escape_sequences_length) = le_u8(i)?;
+    let (i, _) = take(64usize)(i)?; // reserved
+    let (i, application_use_length) = both_endian16(i)?;
+    let (i, application_use) = take(application_use_length)(i)?;
+    let (i, escape_sequences) = take(escape_sequences_length)(i)?;
+
+    Ok((
+        i,
+        ExtendedAttributes {
+            owner,
+            group,
+            permissions,
+            timestamps: Timestamps {
+                creation,
+                modify,
+                expiration,
+                effective,
+                ..Timestamps::default()
+            },
+            record_format,
+            record_attributes,
+            record_length,
+            system_identifier: String::from_utf8_lossy(system_identifier)
+                .trim_end_matches(&[' ', '\0'][..])
+                .to_string(),
+            system_use: system_use.to_vec(),
+            version,
+            application_use: application_use.to_vec(),
+            escape_sequences: escape_sequences.to_vec(),
+        },
+    ))
+}
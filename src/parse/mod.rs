@@ -1,9 +1,20 @@
 // SPDX-License-Identifier: (MIT OR Apache-2.0)
 
+mod boot_catalog;
 mod both_endian;
 mod date_time;
 mod directory_entry;
+mod extended_attributes;
+mod path_table;
+mod susp;
 mod volume_descriptor;
 
-pub(crate) use self::directory_entry::{DirectoryEntryHeader, FileFlags};
-pub(crate) use self::volume_descriptor::VolumeDescriptor;
+pub(crate) use self::boot_catalog::{boot_entry, section_header, validation_entry};
+pub(crate) use self::directory_entry::{DirectoryEntryHeader, DirectoryRecord, FileFlags};
+pub use self::directory_entry::{XaAttributes, XaRecord};
+pub(crate) use self::extended_attributes::extended_attribute_record;
+pub(crate) use self::path_table::path_table;
+pub(crate) use self::susp::{
+    susp_entries, NameFlags, SuspEntry, SymlinkComponentFlags, TimestampFlags,
+};
+pub(crate) use self::volume_descriptor::{decode_ucs2, VolumeDescriptor};
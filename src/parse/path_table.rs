@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use nom::bytes::complete::take;
+use nom::multi::many0;
+use nom::number::complete::*;
+use nom::number::Endianness;
+use nom::IResult;
+
+use crate::path_table::PathTableEntry;
+
+fn path_table_entry(endianness: Endianness) -> impl Fn(&[u8]) -> IResult<&[u8], PathTableEntry> {
+    move |i: &[u8]| {
+        let (i, identifier_length) = le_u8(i)?;
+        if identifier_length == 0 {
+            // Padding after the last entry
+            return Err(nom::Err::Error(nom::error::Error::new(
+                i,
+                nom::error::ErrorKind::Eof,
+            )));
+        }
+        let (i, extended_attribute_record_length) = le_u8(i)?;
+        let (i, extent_loc) = u32(endianness)(i)?;
+        let (i, parent) = u16(endianness)(i)?;
+        let (i, identifier) = take(identifier_length)(i)?;
+        // The identifier is padded to an even length
+        let (i, _) = take(identifier_length % 2)(i)?;
+
+        let identifier = match identifier {
+            // The root directory
+            b"\0" => String::new(),
+            _ => String::from_utf8_lossy(identifier).into_owned(),
+        };
+
+        Ok((
+            i,
+            PathTableEntry {
+                identifier,
+                extended_attribute_record_length,
+                extent_loc,
+                parent,
+            },
+        ))
+    }
+}
+
+/// Parse an L-type (little endian) or M-type (big endian) path table
+pub(crate) fn path_table(i: &[u8], endianness: Endianness) -> IResult<&[u8], Vec<PathTableEntry>> {
+    many0(path_table_entry(endianness))(i)
+}
@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+// System Use Sharing Protocol (SUSP), as used by Rock Ridge, stores a
+// sequence of tagged entries in the system use area of each directory
+// record.
+
+use nom::bytes::complete::{tag, take};
+use nom::combinator::{map, opt};
+use nom::multi::{length_data, many0};
+use nom::number::complete::le_u8;
+use nom::sequence::pair;
+use nom::IResult;
+
+use time::OffsetDateTime;
+
+use super::both_endian::both_endian32;
+use super::date_time::{date_time, date_time_ascii};
+
+bitflags! {
+    #[derive(Clone, Debug)]
+    pub struct NameFlags: u8 {
+        const CONTINUE = 1 << 0;
+        const CURRENT = 1 << 1;
+        const PARENT = 1 << 2;
+    }
+}
+
+bitflags! {
+    #[derive(Clone, Debug)]
+    pub struct SymlinkComponentFlags: u8 {
+        const CONTINUE = 1 << 0;
+        const CURRENT = 1 << 1;
+        const PARENT = 1 << 2;
+        const ROOT = 1 << 3;
+        const VOLUME_ROOT = 1 << 4;
+        const HOST = 1 << 5;
+    }
+}
+
+bitflags! {
+    #[derive(Clone, Debug)]
+    pub struct TimestampFlags: u8 {
+        const CREATION = 1 << 0;
+        const MODIFY = 1 << 1;
+        const ACCESS = 1 << 2;
+        const ATTRIBUTES = 1 << 3;
+        const BACKUP = 1 << 4;
+        const EXPIRATION = 1 << 5;
+        const EFFECTIVE = 1 << 6;
+        // Timestamps use the 17 byte format of volume descriptors, rather
+        // than the 7 byte format of directory records
+        const LONG_FORM = 1 << 7;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum SuspEntry {
+    /// CE: the entries continue in another block
+    ContinuationArea {
+        block: u32,
+        offset: u32,
+        length: u32,
+    },
+    /// SP: only valid in the `.` entry of the root directory
+    SharingProtocol {
+        skip: u8,
+    },
+    /// NM: (part of) the Rock Ridge alternate name
+    AlternateName {
+        flags: NameFlags,
+        name: Vec<u8>,
+    },
+    /// PX: POSIX file attributes
+    PosixAttributes {
+        mode: u32,
+        links: u32,
+        uid: u32,
+        gid: u32,
+        serial: Option<u32>,
+    },
+    /// PN: device number, as the high and low 32 bits of a `dev_t`
+    DeviceNumber {
+        high: u32,
+        low: u32,
+    },
+    /// SL: (part of) the target of a symbolic link
+    SymbolicLink {
+        components: Vec<(SymlinkComponentFlags, Vec<u8>)>,
+    },
+    /// CL: the location of a directory relocated to here
+    ChildLink {
+        loc: u32,
+    },
+    /// PL: the original parent of a relocated directory
+    ParentLink {
+        loc: u32,
+    },
+    /// RE: the directory has been relocated, and should be hidden
+    Relocated,
+    /// TF: timestamps, in the order of the flags that are set
+    Timestamps {
+        flags: TimestampFlags,
+        times: Vec<OffsetDateTime>,
+    },
+    Unknown,
+}
+
+/// Parse the SUSP entries of a system use area. Parsing stops at the first
+/// malformed entry, keeping the entries before it, so a damaged area loses
+/// only part of its extensions.
+pub(crate) fn susp_entries(mut i: &[u8]) -> Vec<SuspEntry> {
+    let mut entries = Vec::new();
+    // Anything too short to hold an entry header is padding.
+    while i.len() >= 4 && i[2] >= 4 {
+        let (rest, (signature, data)) = match susp_entry(i) {
+            Ok(entry) => entry,
+            Err(_) => break,
+        };
+        if signature == b"ST" {
+            break;
+        }
+        match susp_entry_data(signature, data) {
+            Ok((_, entry)) => entries.push(entry),
+            Err(_) => break,
+        }
+        i = rest;
+    }
+    entries
+}
+
+fn susp_entry_data<'a>(signature: &[u8], data: &'a [u8]) -> IResult<&'a [u8], SuspEntry> {
+    match signature {
+        b"CE" => continuation_area(data),
+        b"SP" => sharing_protocol(data),
+        b"NM" => alternate_name(data),
+        b"PX" => posix_attributes(data),
+        b"PN" => device_number(data),
+        b"SL" => symbolic_link(data),
+        b"TF" => timestamps(data),
+        b"CL" => map(both_endian32, |loc| SuspEntry::ChildLink { loc })(data),
+        b"PL" => map(both_endian32, |loc| SuspEntry::ParentLink { loc })(data),
+        b"RE" => Ok((data, SuspEntry::Relocated)),
+        _ => Ok((data, SuspEntry::Unknown)),
+    }
+}
+
+fn susp_entry(i: &[u8]) -> IResult<&[u8], (&[u8], &[u8])> {
+    let (i, signature) = take(2usize)(i)?;
+    let (i, length) = le_u8(i)?;
+    let (i, _version) = le_u8(i)?;
+    let (i, data) = take(length as usize - 4)(i)?;
+    Ok((i, (signature, data)))
+}
+
+fn continuation_area(i: &[u8]) -> IResult<&[u8], SuspEntry> {
+    let (i, block) = both_endian32(i)?;
+    let (i, offset) = both_endian32(i)?;
+    let (i, length) = both_endian32(i)?;
+    Ok((
+        i,
+        SuspEntry::ContinuationArea {
+            block,
+            offset,
+            length,
+        },
+    ))
+}
+
+fn sharing_protocol(i: &[u8]) -> IResult<&[u8], SuspEntry> {
+    let (i, _) = tag(b"\xbe\xef")(i)?;
+    let (i, skip) = le_u8(i)?;
+    Ok((i, SuspEntry::SharingProtocol { skip }))
+}
+
+fn alternate_name(i: &[u8]) -> IResult<&[u8], SuspEntry> {
+    let (name, flags) = le_u8(i)?;
+    Ok((
+        &[],
+        SuspEntry::AlternateName {
+            flags: NameFlags::from_bits_truncate(flags),
+            name: name.to_vec(),
+        },
+    ))
+}
+
+fn posix_attributes(i: &[u8]) -> IResult<&[u8], SuspEntry> {
+    let (i, mode) = both_endian32(i)?;
+    let (i, links) = both_endian32(i)?;
+    let (i, uid) = both_endian32(i)?;
+    let (i, gid) = both_endian32(i)?;
+    // The serial number was added in RRIP 1.12
+    let (i, serial) = opt(both_endian32)(i)?;
+    Ok((
+        i,
+        SuspEntry::PosixAttributes {
+            mode,
+            links,
+            uid,
+            gid,
+            serial,
+        },
+    ))
+}
+
+fn device_number(i: &[u8]) -> IResult<&[u8], SuspEntry> {
+    let (i, high) = both_endian32(i)?;
+    let (i, low) = both_endian32(i)?;
+    Ok((i, SuspEntry::DeviceNumber { high, low }))
+}
+
+fn symbolic_link(i: &[u8]) -> IResult<&[u8], SuspEntry> {
+    // The flags of the SL entry itself only say whether another SL entry
+    // follows, which is implied by the component flags.
+    let (i, _flags) = le_u8(i)?;
+    let (i, components) = many0(pair(le_u8, length_data(le_u8)))(i)?;
+    Ok((
+        i,
+        SuspEntry::SymbolicLink {
+            components: components
+                .into_iter()
+                .map(|(flags, content)| {
+                    (
+                        SymlinkComponentFlags::from_bits_truncate(flags),
+                        content.to_vec(),
+                    )
+                })
+                .collect(),
+        },
+    ))
+}
+
+fn timestamps(i: &[u8]) -> IResult<&[u8], SuspEntry> {
+    let (mut i, flags) = le_u8(i)?;
+    let flags = TimestampFlags::from_bits_truncate(flags);
+
+    let count = (flags.bits() & !TimestampFlags::LONG_FORM.bits()).count_ones();
+    let mut times = Vec::new();
+    for _ in 0..count {
+        let (rest, time) = if flags.contains(TimestampFlags::LONG_FORM) {
+            date_time_ascii(i)?
+        } else {
+            date_time(i)?
+        };
+        i = rest;
+        times.push(time);
+    }
+
+    Ok((i, SuspEntry::Timestamps { flags, times }))
+}
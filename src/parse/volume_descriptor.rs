@@ -13,7 +13,7 @@ use super::date_time::date_time_ascii;
 use super::directory_entry::{directory_entry, DirectoryEntryHeader};
 use crate::ISOError;
 
-#[allow(dead_code)]
+#[allow(dead_code, clippy::large_enum_variant, clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
 pub(crate) enum VolumeDescriptor {
     Primary {
@@ -27,6 +27,48 @@ pub(crate) enum VolumeDescriptor {
         path_table_size: u32,
         path_table_loc: u32,
         optional_path_table_loc: u32,
+        path_table_loc_be: u32,
+        optional_path_table_loc_be: u32,
+
+        root_directory_entry: DirectoryEntryHeader,
+        root_directory_entry_identifier: String,
+
+        volume_set_identifier: String,
+        publisher_identifier: String,
+        data_preparer_identifier: String,
+        application_identifier: String,
+        copyright_file_identifier: String,
+        abstract_file_identifier: String,
+        bibliographic_file_identifier: String,
+
+        creation_time: OffsetDateTime,
+        modification_time: OffsetDateTime,
+        expiration_time: OffsetDateTime,
+        effective_time: OffsetDateTime,
+
+        file_structure_version: u8,
+    },
+    /// Supplementary volume descriptor, as used by Joliet. With version 2,
+    /// this is an enhanced volume descriptor (ISO 9660:1999).
+    Supplementary {
+        version: u8,
+        volume_flags: u8,
+        escape_sequences: Vec<u8>,
+        /// Joliet level (1 to 3), detected from the escape sequences
+        joliet_level: Option<u8>,
+
+        system_identifier: String,
+        volume_identifier: String,
+        volume_space_size: u32,
+        volume_set_size: u16,
+        volume_sequence_number: u16,
+        logical_block_size: u16,
+
+        path_table_size: u32,
+        path_table_loc: u32,
+        optional_path_table_loc: u32,
+        path_table_loc_be: u32,
+        optional_path_table_loc_be: u32,
 
         root_directory_entry: DirectoryEntryHeader,
         root_directory_entry_identifier: String,
@@ -51,6 +93,13 @@ pub(crate) enum VolumeDescriptor {
         boot_identifier: String,
         data: Vec<u8>,
     },
+    VolumePartition {
+        system_identifier: String,
+        partition_identifier: String,
+        location: u32,
+        size: u32,
+        system_use: Vec<u8>,
+    },
     VolumeDescriptorSetTerminator,
 }
 
@@ -69,6 +118,36 @@ fn take_string_trim(count: usize) -> impl Fn(&[u8]) -> IResult<&[u8], String> {
     }
 }
 
+// Joliet strings are UCS-2, big endian
+fn take_ucs2_string_trim(count: usize) -> impl Fn(&[u8]) -> IResult<&[u8], String> {
+    move |i: &[u8]| {
+        map(take(count), |x: &[u8]| {
+            decode_ucs2(x)
+                .trim_end_matches(&[' ', '\0'][..])
+                .to_string()
+        })(i)
+    }
+}
+
+pub(crate) fn decode_ucs2(bytes: &[u8]) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|x| u16::from_be_bytes([x[0], x[1]]));
+    char::decode_utf16(units)
+        .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn joliet_level(escape_sequences: &[u8]) -> Option<u8> {
+    // Only the first escape sequence is considered
+    match escape_sequences.get(..3)? {
+        b"%/@" => Some(1),
+        b"%/C" => Some(2),
+        b"%/E" => Some(3),
+        _ => None,
+    }
+}
+
 fn boot_record(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
     let (i, (boot_system_identifier, boot_identifier, data)) = tuple((
         take_string_trim(32usize),
@@ -85,26 +164,75 @@ fn boot_record(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
     ))
 }
 
+fn volume_partition_descriptor(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
+    let (i, _) = take(1usize)(i)?; // unused
+    let (i, system_identifier) = take_string_trim(32usize)(i)?;
+    let (i, partition_identifier) = take_string_trim(32usize)(i)?;
+    let (i, location) = both_endian32(i)?;
+    let (i, size) = both_endian32(i)?;
+    let (i, system_use) = take(1960usize)(i)?;
+    Ok((
+        i,
+        VolumeDescriptor::VolumePartition {
+            system_identifier,
+            partition_identifier,
+            location,
+            size,
+            system_use: system_use.to_vec(),
+        },
+    ))
+}
+
 fn volume_descriptor(i: &[u8]) -> IResult<&[u8], Option<VolumeDescriptor>> {
     let (i, type_code) = le_u8(i)?;
-    let (i, _) = tag("CD001\u{1}")(i)?;
+    let (i, _) = tag("CD001")(i)?;
+    let (i, version) = le_u8(i)?;
     match type_code {
         0 => map(boot_record, Some)(i),
         1 => map(primary_descriptor, Some)(i),
-        //2 => map(supplementary_volume_descriptor, Some)(i),
-        //3 => map!(volume_partition_descriptor, Some)(i),
+        2 => map(|i| supplementary_descriptor(i, version), Some)(i),
+        3 => map(volume_partition_descriptor, Some)(i),
         255 => Ok((i, Some(VolumeDescriptor::VolumeDescriptorSetTerminator))),
         _ => Ok((i, None)),
     }
 }
 
 fn primary_descriptor(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
-    let (i, _) = take(1usize)(i)?; // padding
-    let (i, system_identifier) = take_string_trim(32usize)(i)?;
-    let (i, volume_identifier) = take_string_trim(32usize)(i)?;
+    primary_or_supplementary_descriptor(i, None)
+}
+
+fn supplementary_descriptor(i: &[u8], version: u8) -> IResult<&[u8], VolumeDescriptor> {
+    primary_or_supplementary_descriptor(i, Some(version))
+}
+
+// The primary and supplementary volume descriptors share a layout, except
+// that the latter uses some fields that are unused padding in the former.
+fn primary_or_supplementary_descriptor(
+    i: &[u8],
+    supplementary_version: Option<u8>,
+) -> IResult<&[u8], VolumeDescriptor> {
+    // The escape sequences say how strings are encoded, but come after
+    // some strings.
+    let joliet_level = match i.get(81..113) {
+        Some(escape_sequences) if supplementary_version.is_some() => joliet_level(escape_sequences),
+        _ => None,
+    };
+    let take_string = |count| {
+        move |i| {
+            if joliet_level.is_some() {
+                take_ucs2_string_trim(count)(i)
+            } else {
+                take_string_trim(count)(i)
+            }
+        }
+    };
+
+    let (i, volume_flags) = le_u8(i)?;
+    let (i, system_identifier) = take_string(32usize)(i)?;
+    let (i, volume_identifier) = take_string(32usize)(i)?;
     let (i, _) = take(8usize)(i)?; // padding
     let (i, volume_space_size) = both_endian32(i)?;
-    let (i, _) = take(32usize)(i)?; // padding
+    let (i, escape_sequences) = take(32usize)(i)?;
     let (i, volume_set_size) = both_endian16(i)?;
     let (i, volume_sequence_number) = both_endian16(i)?;
     let (i, logical_block_size) = both_endian16(i)?;
@@ -112,18 +240,18 @@ fn primary_descriptor(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
     let (i, path_table_size) = both_endian32(i)?;
     let (i, path_table_loc) = le_u32(i)?;
     let (i, optional_path_table_loc) = le_u32(i)?;
-    let (i, _) = take(4usize)(i)?; // path_table_loc_be
-    let (i, _) = take(4usize)(i)?; // optional_path_table_loc_be
+    let (i, path_table_loc_be) = be_u32(i)?;
+    let (i, optional_path_table_loc_be) = be_u32(i)?;
 
     let (i, root_directory_entry) = directory_entry(i)?;
 
-    let (i, volume_set_identifier) = take_string_trim(128)(i)?;
-    let (i, publisher_identifier) = take_string_trim(128)(i)?;
-    let (i, data_preparer_identifier) = take_string_trim(128)(i)?;
-    let (i, application_identifier) = take_string_trim(128)(i)?;
-    let (i, copyright_file_identifier) = take_string_trim(38)(i)?;
-    let (i, abstract_file_identifier) = take_string_trim(36)(i)?;
-    let (i, bibliographic_file_identifier) = take_string_trim(37)(i)?;
+    let (i, volume_set_identifier) = take_string(128)(i)?;
+    let (i, publisher_identifier) = take_string(128)(i)?;
+    let (i, data_preparer_identifier) = take_string(128)(i)?;
+    let (i, application_identifier) = take_string(128)(i)?;
+    let (i, copyright_file_identifier) = take_string(38)(i)?;
+    let (i, abstract_file_identifier) = take_string(36)(i)?;
+    let (i, bibliographic_file_identifier) = take_string(37)(i)?;
 
     let (i, creation_time) = date_time_ascii(i)?;
     let (i, modification_time) = date_time_ascii(i)?;
@@ -132,8 +260,49 @@ fn primary_descriptor(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
 
     let (i, file_structure_version) = le_u8(i)?;
 
-    Ok((
-        i,
+    let root_directory_entry_identifier =
+        String::from_utf8_lossy(root_directory_entry.1).into_owned();
+    let root_directory_entry = root_directory_entry.0;
+
+    let descriptor = if let Some(version) = supplementary_version {
+        VolumeDescriptor::Supplementary {
+            version,
+            volume_flags,
+            escape_sequences: escape_sequences.to_vec(),
+            joliet_level,
+
+            system_identifier,
+            volume_identifier,
+            volume_space_size,
+            volume_set_size,
+            volume_sequence_number,
+            logical_block_size,
+
+            path_table_size,
+            path_table_loc,
+            optional_path_table_loc,
+            path_table_loc_be,
+            optional_path_table_loc_be,
+
+            root_directory_entry,
+            root_directory_entry_identifier,
+
+            volume_set_identifier,
+            publisher_identifier,
+            data_preparer_identifier,
+            application_identifier,
+            copyright_file_identifier,
+            abstract_file_identifier,
+            bibliographic_file_identifier,
+
+            creation_time,
+            modification_time,
+            expiration_time,
+            effective_time,
+
+            file_structure_version,
+        }
+    } else {
         VolumeDescriptor::Primary {
             system_identifier,
             volume_identifier,
@@ -145,9 +314,11 @@ fn primary_descriptor(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
             path_table_size,
             path_table_loc,
             optional_path_table_loc,
+            path_table_loc_be,
+            optional_path_table_loc_be,
 
-            root_directory_entry: root_directory_entry.0,
-            root_directory_entry_identifier: root_directory_entry.1,
+            root_directory_entry,
+            root_directory_entry_identifier,
 
             volume_set_identifier,
             publisher_identifier,
@@ -163,6 +334,8 @@ fn primary_descriptor(i: &[u8]) -> IResult<&[u8], VolumeDescriptor> {
             effective_time,
 
             file_structure_version,
-        },
-    ))
+        }
+    };
+
+    Ok((i, descriptor))
 }
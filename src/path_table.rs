@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use nom::number::Endianness;
+
+use crate::parse::path_table;
+use crate::{FileRef, ISO9660Reader, ISOError, Result};
+
+/// Byte order of a path table; images record the table in both
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathTableType {
+    /// Little endian
+    L,
+    /// Big endian
+    M,
+}
+
+/// A directory, as listed in the path table
+#[derive(Clone, Debug)]
+pub struct PathTableEntry {
+    /// ISO 9660 identifier of the directory; empty for the root
+    pub identifier: String,
+    pub extended_attribute_record_length: u8,
+    pub extent_loc: u32,
+    /// Index of the parent directory in the path table, starting at 1. The
+    /// root is its own parent.
+    pub parent: u16,
+}
+
+/// The path table lists every directory of the hierarchy, ordered by
+/// depth, so directories can be found without reading their parents.
+#[derive(Clone, Debug)]
+pub struct PathTable {
+    pub entries: Vec<PathTableEntry>,
+}
+
+impl PathTable {
+    pub(crate) fn read<T: ISO9660Reader>(
+        file: &FileRef<T>,
+        loc: u32,
+        size: u32,
+        table_type: PathTableType,
+    ) -> Result<PathTable> {
+        let block_size = file.block_size() as usize;
+        let mut buf = vec![0; (size as usize).div_ceil(block_size) * block_size];
+        let count = file.read_at(&mut buf, loc as u64)?;
+        if count != buf.len() {
+            return Err(ISOError::ReadSize(buf.len(), count));
+        }
+
+        let endianness = match table_type {
+            PathTableType::L => Endianness::Little,
+            PathTableType::M => Endianness::Big,
+        };
+        let entries = path_table(&buf[..size as usize], endianness)?.1;
+        if entries.is_empty() {
+            return Err(ISOError::InvalidFs("Empty path table"));
+        }
+        Ok(PathTable { entries })
+    }
+
+    /// Find the directory with the given parent index and identifier,
+    /// returning its index. ISO 9660 identifiers are compared ignoring
+    /// case.
+    pub fn find(&self, parent: u16, identifier: &str) -> Option<u16> {
+        // Index 1 (the root) is never a child of another directory
+        let index =
+            self.entries.iter().skip(1).position(|x| {
+                x.parent == parent && x.identifier.eq_ignore_ascii_case(identifier)
+            })?;
+        Some(index as u16 + 2)
+    }
+
+    /// The entry with the given index, starting at 1
+    pub fn get(&self, index: u16) -> Option<&PathTableEntry> {
+        self.entries.get((index as usize).checked_sub(1)?)
+    }
+}
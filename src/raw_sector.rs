@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::ecc;
+use crate::{ISO9660Reader, ISOError, Result, SectorStatus};
+
+// Sync pattern at the start of each raw 2352 byte sector
+pub(crate) const SYNC: [u8; 12] = [
+    0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00,
+];
+const SUBCHANNEL_SIZE: usize = 96;
+/// Size of the data of a Mode 2 Form 2 sector, which has no ECC
+pub(crate) const FORM2_DATA_SIZE: usize = 2324;
+
+/// How sectors are stored in an image
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectorLayout {
+    /// Only the 2048 bytes of user data, as in an ISO file
+    Cooked,
+    /// Mode 2 sectors without the sync pattern and header, starting with
+    /// the subheader
+    Raw2336,
+    /// Whole sectors, with sync pattern, header, and the EDC and ECC of
+    /// Mode 1 or Mode 2 Form 1 sectors
+    Raw2352,
+}
+
+impl SectorLayout {
+    /// Size of a stored sector, not including any subchannel data
+    pub fn sector_size(self) -> usize {
+        match self {
+            SectorLayout::Cooked => 2048,
+            SectorLayout::Raw2336 => 2336,
+            SectorLayout::Raw2352 => 2352,
+        }
+    }
+}
+
+// The layouts `RawSectorReader::new` tries, with or without subchannel
+const LAYOUTS: [(SectorLayout, bool); 5] = [
+    (SectorLayout::Cooked, false),
+    (SectorLayout::Raw2352, false),
+    (SectorLayout::Raw2352, true),
+    (SectorLayout::Raw2336, false),
+    (SectorLayout::Raw2336, true),
+];
+
+/// Reads the 2048 bytes of user data of each sector of an image of raw
+/// sectors, like a BIN file
+#[derive(Debug)]
+pub struct RawSectorReader<T: Read + Seek> {
+    inner: T,
+    layout: SectorLayout,
+    /// Whether each sector is followed by 96 bytes of subchannel data
+    subchannel: bool,
+    /// Whether sectors are checked and repaired with their ECC as they are
+    /// read
+    error_correction: bool,
+    sector: Vec<u8>,
+}
+
+impl<T: Read + Seek> RawSectorReader<T> {
+    /// Detect the layout of the sectors, from the sync pattern and mode
+    /// byte, and where the primary volume descriptor is found
+    pub fn new(inner: T) -> Result<RawSectorReader<T>> {
+        let mut reader = RawSectorReader::with_layout(inner, SectorLayout::Cooked, false);
+        let mut buf = [0; 2048];
+        for &(layout, subchannel) in &LAYOUTS {
+            reader.layout = layout;
+            reader.subchannel = subchannel;
+            if reader.read_at(&mut buf, 16)? != 2048 {
+                continue;
+            }
+            let synced = layout != SectorLayout::Raw2352 || reader.sector.starts_with(&SYNC);
+            if synced && &buf[1..6] == b"CD001" {
+                return Ok(reader);
+            }
+        }
+        Err(ISOError::InvalidFs("Unrecognized sector layout"))
+    }
+
+    pub fn with_layout(inner: T, layout: SectorLayout, subchannel: bool) -> RawSectorReader<T> {
+        RawSectorReader {
+            inner,
+            layout,
+            subchannel,
+            error_correction: false,
+            sector: Vec::new(),
+        }
+    }
+
+    pub fn layout(&self) -> SectorLayout {
+        self.layout
+    }
+
+    pub fn has_subchannel(&self) -> bool {
+        self.subchannel
+    }
+
+    /// Size of each sector in the image, including subchannel data
+    pub fn sector_size(&self) -> usize {
+        self.layout.sector_size() + if self.subchannel { SUBCHANNEL_SIZE } else { 0 }
+    }
+
+    /// Repair damaged Mode 1 and Mode 2 Form 1 sectors as they are read,
+    /// using their ECC. Reading a sector that can't be repaired then fails,
+    /// instead of returning damaged data.
+    pub fn set_error_correction(&mut self, error_correction: bool) {
+        self.error_correction = error_correction;
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Read a stored sector, without subchannel data. Returns `false` at
+    /// the end of the image.
+    fn read_sector(&mut self, lba: u64) -> io::Result<bool> {
+        let size = self.layout.sector_size();
+        self.sector.resize(size, 0);
+        self.inner
+            .seek(SeekFrom::Start(lba * self.sector_size() as u64))?;
+        match self.inner.read_exact(&mut self.sector) {
+            Ok(()) => Ok(true),
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Check the sector last read, repairing it if `correct` is set
+    fn check_sector(&mut self, correct: bool) -> SectorStatus {
+        match self.layout {
+            SectorLayout::Cooked => SectorStatus::Unchecked,
+            SectorLayout::Raw2352 => ecc::check(&mut self.sector, correct),
+            SectorLayout::Raw2336 => {
+                // Restore the sync pattern and header; the header of Mode 2
+                // sectors is not covered by the EDC or ECC
+                let mut sector = SYNC.to_vec();
+                sector.extend_from_slice(&[0, 0, 0, 2]);
+                sector.extend_from_slice(&self.sector);
+                let status = ecc::check(&mut sector, correct);
+                self.sector.copy_from_slice(&sector[16..]);
+                status
+            }
+        }
+    }
+
+    /// Offset of the user data in the stored sector
+    fn data_offset(&self) -> usize {
+        match self.layout {
+            SectorLayout::Cooked => 0,
+            // After the subheader
+            SectorLayout::Raw2336 => 8,
+            SectorLayout::Raw2352 => match self.sector[15] {
+                // After the sync pattern, header and subheader
+                2 => 24,
+                _ => 16,
+            },
+        }
+    }
+}
+
+impl<T: Read + Seek> ISO9660Reader for RawSectorReader<T> {
+    fn read_at(&mut self, buf: &mut [u8], lba: u64) -> io::Result<usize> {
+        let mut count = 0;
+        for (i, chunk) in buf.chunks_mut(2048).enumerate() {
+            if !self.read_sector(lba + i as u64)? {
+                break;
+            }
+            if self.error_correction && self.check_sector(true) == SectorStatus::Uncorrectable {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Uncorrectable sector",
+                ));
+            }
+            let offset = self.data_offset();
+            chunk.copy_from_slice(&self.sector[offset..offset + chunk.len()]);
+            count += chunk.len();
+        }
+        Ok(count)
+    }
+
+    fn sector_status(&mut self, lba: u64) -> io::Result<SectorStatus> {
+        if !self.read_sector(lba)? {
+            return Ok(SectorStatus::Missing);
+        }
+        Ok(self.check_sector(false))
+    }
+
+    fn has_raw_sectors(&self) -> bool {
+        self.layout != SectorLayout::Cooked
+    }
+
+    fn read_form2_at(&mut self, buf: &mut [u8], lba: u64) -> io::Result<usize> {
+        if !self.has_raw_sectors() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Mode 2 Form 2 sectors need raw sectors",
+            ));
+        }
+
+        let mut count = 0;
+        for (i, chunk) in buf.chunks_mut(FORM2_DATA_SIZE).enumerate() {
+            if !self.read_sector(lba + i as u64)? {
+                break;
+            }
+            if self.layout == SectorLayout::Raw2352 && self.sector[15] != 2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Not a Mode 2 sector",
+                ));
+            }
+            let offset = self.data_offset();
+            chunk.copy_from_slice(&self.sector[offset..offset + chunk.len()]);
+            count += chunk.len();
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use std::collections::BTreeMap;
+
+use crate::{DirectoryEntry, ISO9660Reader, ISODirectory, Result};
+
+/// A difference between the hierarchies of two sessions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added(String),
+    Removed(String),
+    /// The entry exists in both, but its content or type differs
+    Modified(String),
+}
+
+/// What identifies the content of an entry. Later sessions refer to
+/// unchanged files by their existing extents, so a file whose location or
+/// size differs has been rewritten.
+#[derive(PartialEq, Eq)]
+enum Content {
+    Directory,
+    File { loc: u32, size: u64 },
+    Symlink(String),
+    Special(u32, Option<(u32, u32)>),
+}
+
+pub(crate) fn diff<T: ISO9660Reader>(
+    old: &ISODirectory<T>,
+    new: &ISODirectory<T>,
+) -> Result<Vec<Change>> {
+    let mut old_entries = BTreeMap::new();
+    walk(old, &mut old_entries)?;
+    let mut new_entries = BTreeMap::new();
+    walk(new, &mut new_entries)?;
+
+    let mut changes = Vec::new();
+    for (path, content) in &old_entries {
+        match new_entries.get(path) {
+            None => changes.push(Change::Removed(path.clone())),
+            Some(new_content) if new_content != content => {
+                changes.push(Change::Modified(path.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for path in new_entries.keys() {
+        if !old_entries.contains_key(path) {
+            changes.push(Change::Added(path.clone()));
+        }
+    }
+    changes.sort_by(|a, b| path(a).cmp(path(b)));
+
+    Ok(changes)
+}
+
+fn path(change: &Change) -> &str {
+    match change {
+        Change::Added(path) | Change::Removed(path) | Change::Modified(path) => path,
+    }
+}
+
+fn walk<T: ISO9660Reader>(
+    dir: &ISODirectory<T>,
+    entries: &mut BTreeMap<String, Content>,
+) -> Result<()> {
+    dir.walk(&mut |path, entry| {
+        let content = match entry {
+            DirectoryEntry::Directory(_) => Content::Directory,
+            DirectoryEntry::File(file) => Content::File {
+                loc: file.header.extent_loc,
+                size: file.size(),
+            },
+            DirectoryEntry::Symlink(link) => Content::Symlink(link.target().to_string()),
+            DirectoryEntry::Special(special) => {
+                Content::Special(special.metadata().mode, special.device())
+            }
+        };
+        entries.insert(path.to_string(), content);
+        Ok(())
+    })
+}
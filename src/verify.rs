@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+use std::ops::Range;
+
+use crate::{DirectoryEntry, FileRef, ISO9660Reader, ISODirectory, Result, SectorStatus};
+
+/// A damaged sector, found by `ISO9660::verify`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BadSector {
+    pub lba: u64,
+    /// `Correctable`, `Uncorrectable`, or `Missing` if the image is shorter
+    /// than the volume
+    pub status: SectorStatus,
+    /// Path of the file or directory the sector belongs to, if any, like
+    /// `DIR/FILE.TXT`, or `/` for the root directory
+    pub path: Option<String>,
+}
+
+pub(crate) fn verify<T: ISO9660Reader>(
+    file: &FileRef<T>,
+    root: &ISODirectory<T>,
+    sectors: Range<u64>,
+) -> Result<Vec<BadSector>> {
+    let mut bad = Vec::new();
+    // The reader is borrowed again to read directories below
+    let mut reader = file.reader();
+    for lba in sectors {
+        match reader.sector_status(lba)? {
+            SectorStatus::Unchecked | SectorStatus::Good => {}
+            status => bad.push(BadSector {
+                lba,
+                status,
+                path: None,
+            }),
+        }
+    }
+    drop(reader);
+    if bad.is_empty() {
+        return Ok(bad);
+    }
+
+    let mut owners = Vec::new();
+    let block_size = file.block_size() as u64;
+    let extent = |loc: u32, length: u32| {
+        let start = loc as u64 * block_size;
+        start / 2048..(start + length as u64).div_ceil(2048)
+    };
+    owners.push((
+        extent(root.header.extent_loc, root.header.extent_length),
+        "/".to_string(),
+    ));
+    root.walk(&mut |path, entry| {
+        match entry {
+            DirectoryEntry::Directory(dir) => owners.push((
+                extent(dir.header.extent_loc, dir.header.extent_length),
+                path.to_string(),
+            )),
+            DirectoryEntry::File(file) => {
+                for &(loc, length) in file.extents() {
+                    owners.push((extent(loc, length), path.to_string()));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    for sector in &mut bad {
+        sector.path = owners
+            .iter()
+            .find(|x| x.0.contains(&sector.lba))
+            .map(|x| x.1.clone());
+    }
+    Ok(bad)
+}
@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: (MIT OR Apache-2.0)
+
+/// A volume partition, as described by a volume partition descriptor: a
+/// range of logical blocks set aside for use by some system
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumePartition {
+    pub system_identifier: String,
+    pub identifier: String,
+    /// Location of the first logical block of the partition
+    pub location: u32,
+    /// Size of the partition, in logical blocks
+    pub size: u32,
+    pub system_use: Vec<u8>,
+}
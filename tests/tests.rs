@@ -3,9 +3,13 @@
 extern crate iso9660;
 extern crate md5;
 
-use iso9660::{DirectoryEntry, ISO9660};
+use iso9660::{
+    BadSector, Change, Compression, CsoReader, CueSheet, DirectoryEntry, Disc, Emulation,
+    PathTableType, Platform, RawSectorReader, SectorLayout, SectorStatus, SpecialFileType,
+    TrackMode, XaAttributes, ISO9660,
+};
 use std::fs::File;
-use std::io::Read;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
 #[test]
 fn test_dir() {
@@ -34,6 +38,22 @@ fn test_large_file() {
     assert_eq!(format!("{:x}", hash), "1ebbd3e34237af26da5dc08a4e440464");
 }
 
+#[test]
+fn test_dates() {
+    let fs = ISO9660::new(File::open("test.iso").unwrap()).unwrap();
+
+    // Offsets west of GMT are negative
+    assert_eq!(
+        fs.root.time().to_string(),
+        "2018-03-19 20:52:10.0 -07:00:00"
+    );
+    // The years of volume descriptor dates are written out in full
+    assert_eq!(
+        fs.creation_time().to_string(),
+        "2018-03-20 14:24:03.12 -07:00:00"
+    );
+}
+
 #[test]
 fn test_extra_slashes() {
     let fs = ISO9660::new(File::open("test.iso").unwrap()).unwrap();
@@ -57,3 +77,1015 @@ fn test_large_dir() {
     assert_eq!(dir.contents().map(Result::unwrap).count(), 202);
     assert_eq!(dir.block_count(), 4);
 }
+
+#[test]
+fn test_rock_ridge_names() {
+    let fs = ISO9660::new(File::open("rockridge.iso").unwrap()).unwrap();
+
+    let names = fs
+        .root
+        .contents()
+        .take(6)
+        .map(|x| x.unwrap().identifier().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            ".",
+            "..",
+            "Docs",
+            "README.md",
+            "readme.md",
+            "a_very_long_file_name_that_needs_a_continuation_area_to_hold_all_of_it.txt",
+        ]
+    );
+
+    assert!(fs.open("Docs/Notes.txt").unwrap().is_some());
+    assert!(fs.open("docs/notes.txt").unwrap().is_none());
+
+    let file = match fs.open("readme.md").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file,
+        _ => panic!("Not a file"),
+    };
+    let mut text = String::new();
+    file.read().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "# lower case\n");
+}
+
+#[test]
+fn test_rock_ridge_symlinks() {
+    let fs = ISO9660::new(File::open("rockridge.iso").unwrap()).unwrap();
+
+    let target = |path| match fs.open(path).unwrap().unwrap() {
+        DirectoryEntry::Symlink(link) => link.target().to_string(),
+        _ => panic!("Not a symlink"),
+    };
+    assert_eq!(target("link_to_readme"), "README.md");
+    assert_eq!(target("abs_notes"), "/Docs/Notes.txt");
+    assert_eq!(target("split_link"), "/Docs/Notes.txt");
+    assert_eq!(target("Docs/up"), "../README.md");
+
+    assert!(fs.open("docs_link/Notes.txt").unwrap().is_none());
+    for path in &[
+        "docs_link/Notes.txt",
+        "abs_notes",
+        "split_link",
+        "docs_link/up",
+    ] {
+        match fs.open_follow_symlinks(path).unwrap() {
+            Some(DirectoryEntry::File(_)) => {}
+            _ => panic!("'{}' does not resolve to a file", path),
+        }
+    }
+    assert!(fs.open_follow_symlinks("loop_a").is_err());
+}
+
+#[test]
+fn test_rock_ridge_metadata() {
+    let fs = ISO9660::new(File::open("rockridge.iso").unwrap()).unwrap();
+
+    let metadata = fs.open("Docs/Notes.txt").unwrap().unwrap().metadata();
+    assert_eq!(metadata.mode, 0o100640);
+    assert_eq!(metadata.permissions(), 0o640);
+    assert_eq!((metadata.nlink, metadata.uid, metadata.gid), (1, 1000, 100));
+    assert_eq!(metadata.serial, Some(42));
+
+    let metadata = fs.open("Docs").unwrap().unwrap().metadata();
+    assert_eq!(metadata.mode, 0o40750);
+    assert_eq!(metadata.serial, None);
+    assert_eq!(fs.root.metadata().mode, 0o40755);
+
+    assert_eq!(
+        fs.open("link_to_readme").unwrap().unwrap().metadata().mode,
+        0o120777
+    );
+
+    // No PX entry
+    let metadata = fs.open("readme.md").unwrap().unwrap().metadata();
+    assert_eq!(metadata.mode, 0o100444);
+    assert_eq!((metadata.nlink, metadata.uid, metadata.gid), (1, 0, 0));
+
+    // No Rock Ridge
+    let fs = ISO9660::new(File::open("test.iso").unwrap()).unwrap();
+    assert_eq!(fs.root.metadata().mode, 0o40555);
+}
+
+#[test]
+fn test_rock_ridge_timestamps() {
+    let fs = ISO9660::new(File::open("rockridge.iso").unwrap()).unwrap();
+
+    let timestamps = fs.open("Docs/Notes.txt").unwrap().unwrap().timestamps();
+    assert_eq!(timestamps.creation, None);
+    assert_eq!(
+        timestamps.modify.unwrap().to_string(),
+        "2021-01-02 3:04:05.0 +00:00:00"
+    );
+    assert_eq!(
+        timestamps.access.unwrap().to_string(),
+        "2021-02-03 4:05:06.0 +00:00:00"
+    );
+    assert_eq!(
+        timestamps.attributes.unwrap().to_string(),
+        "2021-03-04 5:06:07.0 +01:00:00"
+    );
+
+    // Long form
+    let timestamps = fs.open("README.md").unwrap().unwrap().timestamps();
+    assert_eq!(
+        timestamps.creation.unwrap().to_string(),
+        "1999-12-31 23:59:59.0 +00:00:00"
+    );
+    assert_eq!(
+        timestamps.modify.unwrap().to_string(),
+        "2021-06-30 12:00:00.5 -08:00:00"
+    );
+    assert_eq!(timestamps.access, None);
+    assert!(timestamps.effective.is_some());
+
+    assert_eq!(
+        fs.open("readme.md").unwrap().unwrap().timestamps().modify,
+        None
+    );
+}
+
+#[test]
+fn test_damaged_records() {
+    let mut image = std::fs::read("rockridge.iso").unwrap();
+    let record =
+        |image: &[u8], name: &[u8]| image.windows(name.len()).position(|x| x == name).unwrap();
+    // A truncated NM entry ends the system use area, leaving the ISO 9660
+    // name; a bad version number makes the record unreadable
+    let nm = record(&image, b"README_1.MD;1NM") + 13;
+    image[nm + 2] = 0x40;
+    let version = record(&image, b"README.MD;1") + 10;
+    image[version] = b'X';
+
+    let fs = ISO9660::new(Cursor::new(image)).unwrap();
+    let entries = fs.root.contents().collect::<Vec<_>>();
+    assert_eq!(entries.iter().filter(|x| x.is_err()).count(), 1);
+    let names = entries
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|x| x.identifier().to_string())
+        .collect::<Vec<_>>();
+    assert!(names.contains(&"README_1.MD".to_string()));
+    assert!(names.contains(&"dev".to_string()));
+    assert!(!names.contains(&"readme.md".to_string()));
+}
+
+#[test]
+fn test_rock_ridge_relocation() {
+    let fs = ISO9660::new(File::open("rockridge.iso").unwrap()).unwrap();
+
+    assert!(fs
+        .root
+        .contents()
+        .all(|x| x.unwrap().identifier() != "rr_moved"));
+    assert!(fs.open("rr_moved").unwrap().is_none());
+
+    let dir = match fs.open("Docs/deep").unwrap().unwrap() {
+        DirectoryEntry::Directory(dir) => dir,
+        _ => panic!("Not a directory"),
+    };
+    assert_eq!(dir.identifier, "deep");
+    assert_eq!(dir.metadata().mode, 0o40700);
+    assert!(dir.find("inner.txt").unwrap().is_some());
+
+    // '..' leads back to the original parent
+    assert!(fs.open("Docs/deep/../Notes.txt").unwrap().is_some());
+
+    // A directory of the same name that holds no relocated directories
+    // isn't hidden
+    let fs = ISO9660::new(File::open("rrmoved.iso").unwrap()).unwrap();
+    assert!(fs
+        .root
+        .contents()
+        .any(|x| x.unwrap().identifier() == "rr_moved"));
+    assert!(fs.open("rr_moved/keep.txt").unwrap().is_some());
+}
+
+#[test]
+fn test_rock_ridge_special_files() {
+    let fs = ISO9660::new(File::open("rockridge.iso").unwrap()).unwrap();
+
+    let special = |path| match fs.open(path).unwrap().unwrap() {
+        DirectoryEntry::Special(special) => (special.file_type(), special.device()),
+        _ => panic!("'{}' is not a special file", path),
+    };
+    assert_eq!(
+        special("dev/null"),
+        (SpecialFileType::CharacterDevice, Some((1, 3)))
+    );
+    assert_eq!(
+        special("dev/sda"),
+        (SpecialFileType::BlockDevice, Some((8, 0)))
+    );
+    assert_eq!(special("dev/initctl"), (SpecialFileType::Fifo, None));
+    assert_eq!(special("dev/log"), (SpecialFileType::Socket, None));
+
+    assert_eq!(fs.open("dev/sda").unwrap().unwrap().metadata().gid, 6);
+}
+
+#[test]
+fn test_joliet() {
+    let fs = ISO9660::new(File::open("joliet.iso").unwrap()).unwrap();
+    assert_eq!(fs.joliet_level(), Some(3));
+
+    let root = fs.joliet_root.as_ref().unwrap();
+    let names = root
+        .contents()
+        .map(|x| x.unwrap().identifier().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [".", "..", "Program Files", "日本語 ünicöde.txt"]);
+
+    let dir = match root.find("program files").unwrap().unwrap() {
+        DirectoryEntry::Directory(dir) => dir,
+        _ => panic!("Not a directory"),
+    };
+    let file = match dir.find("Read Me.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file,
+        _ => panic!("Not a file"),
+    };
+    assert_eq!(file.version, 1);
+    let mut text = String::new();
+    file.read().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "Hello from Windows\r\n");
+
+    // The primary hierarchy is still available
+    assert!(fs.open("program_/readme.txt").unwrap().is_some());
+
+    let fs = ISO9660::new(File::open("test.iso").unwrap()).unwrap();
+    assert!(fs.joliet_root.is_none());
+}
+
+#[test]
+fn test_enhanced_volume_descriptor() {
+    let fs = ISO9660::new(File::open("enhanced.iso").unwrap()).unwrap();
+    assert!(fs.joliet_root.is_none());
+
+    let mut dir = fs.enhanced_root.clone().unwrap();
+    for level in 1..=10 {
+        dir = match dir.find(&format!("level {}", level)).unwrap().unwrap() {
+            DirectoryEntry::Directory(dir) => dir,
+            _ => panic!("Not a directory"),
+        };
+    }
+    match dir.find("file;with;semicolons").unwrap().unwrap() {
+        DirectoryEntry::File(file) => assert_eq!(file.version, 1),
+        _ => panic!("Not a file"),
+    }
+
+    let names = fs
+        .enhanced_root
+        .as_ref()
+        .unwrap()
+        .contents()
+        .skip(3)
+        .map(|x| x.unwrap().identifier().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names[0].len(), 207);
+    assert_eq!(names[1], "trailing.");
+}
+
+#[test]
+fn test_el_torito() {
+    let fs = ISO9660::new(File::open("eltorito.iso").unwrap()).unwrap();
+    let catalog = fs.boot_catalog().unwrap().unwrap();
+    assert_eq!(catalog.platform, Platform::X86);
+    assert_eq!(catalog.manufacturer, "TEST MANUFACTURER");
+
+    let entry = &catalog.default_entry;
+    assert!(entry.bootable);
+    assert_eq!(entry.emulation, Emulation::NoEmulation);
+    assert_eq!(entry.sector_count, 4);
+    let mut image = Vec::new();
+    fs.boot_image(entry).read_to_end(&mut image).unwrap();
+    assert_eq!(image.len(), 2048);
+    assert_eq!(&image[..4], &[0, 1, 2, 3]);
+
+    assert_eq!(catalog.sections.len(), 2);
+    let section = &catalog.sections[0];
+    assert_eq!(section.platform, Platform::Efi);
+    assert_eq!(section.identifier, "UEFI");
+    assert_eq!(section.entries.len(), 1);
+    let mut image = Vec::new();
+    fs.boot_image(&section.entries[0])
+        .read_to_end(&mut image)
+        .unwrap();
+    assert_eq!(image, b"EFI!".repeat(256));
+
+    let section = &catalog.sections[1];
+    assert_eq!(section.platform, Platform::PowerPC);
+    let entry = &section.entries[0];
+    assert!(!entry.bootable);
+    assert_eq!(entry.emulation, Emulation::HardDisk);
+    assert_eq!(entry.load_segment, 0x1000);
+    assert_eq!(entry.system_type, 0x41);
+    assert_eq!(catalog.entries().count(), 3);
+
+    let fs = ISO9660::new(File::open("test.iso").unwrap()).unwrap();
+    assert!(fs.boot_catalog().unwrap().is_none());
+}
+
+#[test]
+fn test_block_size() {
+    for (path, block_size) in [("blocksize512.iso", 512), ("blocksize1024.iso", 1024)] {
+        let fs = ISO9660::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(fs.block_size(), block_size);
+
+        let names = match fs.open("many").unwrap().unwrap() {
+            DirectoryEntry::Directory(dir) => dir
+                .contents()
+                .map(|x| x.unwrap().identifier().to_string())
+                .collect::<Vec<_>>(),
+            _ => panic!("Not a directory"),
+        };
+        assert_eq!(names.len(), 42);
+        assert_eq!(names[2], "file000.txt");
+        assert_eq!(names[41], "file039.txt");
+
+        let mut text = String::new();
+        match fs.open("many/file039.txt").unwrap().unwrap() {
+            DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+            _ => panic!("Not a file"),
+        };
+        assert_eq!(text, "file 39\n");
+
+        let mut text = String::new();
+        match fs.open("big.txt").unwrap().unwrap() {
+            DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+            _ => panic!("Not a file"),
+        };
+        assert_eq!(text.len(), 7700);
+        assert!(text.ends_with("line 00699\n"));
+
+        assert!(fs.open("continued name.txt").unwrap().is_some());
+
+        let dir = match fs.open("many").unwrap().unwrap() {
+            DirectoryEntry::Directory(dir) => dir,
+            _ => panic!("Not a directory"),
+        };
+        // 3072 bytes of directory records
+        assert_eq!(dir.block_count(), 3072 / block_size as u32);
+
+        // Other sessions keep the logical block size
+        let session = &fs.sessions().unwrap()[0];
+        assert_eq!(session.block_size(), block_size);
+        assert!(session.open("many/file039.txt").unwrap().is_some());
+    }
+}
+
+#[test]
+fn test_multi_extent() {
+    let fs = ISO9660::new(File::open("multiextent.iso").unwrap()).unwrap();
+    let data = (0..1250).map(|i| format!("{:08}", i)).collect::<String>();
+
+    let names = fs
+        .root
+        .contents()
+        .map(|x| x.unwrap().identifier().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names.len(), 2 + 2 + 26 + 1);
+    assert_eq!(names[2], "big.dat");
+    assert_eq!(names[3], "after.txt");
+    assert_eq!(names[30], "zlate.dat");
+
+    let file = match fs.open("big.dat").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file,
+        _ => panic!("Not a file"),
+    };
+    assert_eq!(file.size(), 10000);
+    let mut text = String::new();
+    file.read().read_to_string(&mut text).unwrap();
+    assert_eq!(text, data);
+
+    // Seek into the last extent, and read across extents
+    let mut reader = file.read();
+    let mut buf = [0; 16];
+    reader.seek(SeekFrom::Start(8192)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, &data.as_bytes()[8192..8208]);
+    reader.seek(SeekFrom::Start(4088)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, &data.as_bytes()[4088..4104]);
+    reader.seek(SeekFrom::End(-8)).unwrap();
+    let mut text = String::new();
+    reader.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "00001249");
+
+    // Records split over two sectors of the directory
+    let file = match fs.open("zlate.dat").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file,
+        _ => panic!("Not a file"),
+    };
+    let mut text = String::new();
+    file.read().read_to_string(&mut text).unwrap();
+    assert_eq!(text, data[..5000]);
+}
+
+#[test]
+fn test_path_table() {
+    let fs = ISO9660::new(File::open("test.iso").unwrap()).unwrap();
+
+    let l_table = fs.path_table(PathTableType::L).unwrap();
+    let m_table = fs.path_table(PathTableType::M).unwrap();
+    for table in [&l_table, &m_table] {
+        let entries = table
+            .entries
+            .iter()
+            .map(|x| (x.identifier.as_str(), x.parent))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, [("", 1), ("A", 1), ("B", 2), ("C", 3)]);
+    }
+    for (l, m) in l_table.entries.iter().zip(&m_table.entries) {
+        assert_eq!(l.extent_loc, m.extent_loc);
+    }
+    assert_eq!(l_table.find(2, "b"), Some(3));
+    assert_eq!(l_table.find(1, "b"), None);
+
+    // Lookups resolve the leading directories through the path table
+    match fs.open("a/b/c/1").unwrap().unwrap() {
+        DirectoryEntry::File(file) => assert_eq!(file.identifier, "1"),
+        _ => panic!("Not a file"),
+    }
+    match fs.open("a/b").unwrap().unwrap() {
+        DirectoryEntry::Directory(dir) => assert_eq!(dir.identifier, "B"),
+        _ => panic!("Not a directory"),
+    }
+    assert!(fs.open("a/b/d/1").unwrap().is_none());
+    assert!(fs.open("a/b/c/1/2").unwrap().is_none());
+}
+
+#[test]
+fn test_extended_attributes() {
+    let fs = ISO9660::new(File::open("xar.iso").unwrap()).unwrap();
+
+    let file = match fs.open("attrs.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file,
+        _ => panic!("Not a file"),
+    };
+    let mut text = String::new();
+    file.read().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "data after the record\n");
+
+    let attributes = file.extended_attributes().unwrap().unwrap();
+    assert_eq!(attributes.owner, 1000);
+    assert_eq!(attributes.group, 100);
+    assert_eq!(attributes.mode(), 0o540);
+    assert_eq!(attributes.version, 1);
+    assert_eq!(attributes.system_identifier, "TESTSYS");
+    assert_eq!(attributes.application_use, b"application");
+    let modify = attributes.timestamps.modify.unwrap();
+    assert_eq!(modify.year(), 2021);
+    assert_eq!(modify.offset().whole_hours(), 1);
+    assert!(attributes.timestamps.creation.is_some());
+    assert!(attributes.timestamps.expiration.is_none());
+
+    match fs.open("plain.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => assert!(file.extended_attributes().unwrap().is_none()),
+        _ => panic!("Not a file"),
+    }
+}
+
+#[test]
+fn test_volume_partition() {
+    let fs = ISO9660::new(File::open("partition.iso").unwrap()).unwrap();
+    assert!(fs.open("readme.txt").unwrap().is_some());
+
+    let partitions = fs.partitions();
+    assert_eq!(partitions.len(), 1);
+    let partition = &partitions[0];
+    assert_eq!(partition.system_identifier, "ARCHIVER");
+    assert_eq!(partition.identifier, "BACKUP 1");
+    assert_eq!(partition.size, 2);
+
+    let mut data = Vec::new();
+    fs.read_partition(partition).read_to_end(&mut data).unwrap();
+    assert_eq!(data, b"PART".repeat(1024));
+
+    let fs = ISO9660::new(File::open("test.iso").unwrap()).unwrap();
+    assert!(fs.partitions().is_empty());
+}
+
+#[test]
+fn test_multi_session() {
+    let mut file = File::open("multisession.iso").unwrap();
+    let sessions = ISO9660::find_sessions(&mut file).unwrap();
+    assert_eq!(sessions, [0, 48, 96]);
+
+    let read = |fs: &ISO9660<File>, path| {
+        let mut text = String::new();
+        match fs.open(path).unwrap() {
+            Some(DirectoryEntry::File(file)) => file.read().read_to_string(&mut text).unwrap(),
+            _ => return None,
+        };
+        Some(text)
+    };
+
+    // The first session only sees what was written at the time
+    let fs = ISO9660::new(File::open("multisession.iso").unwrap()).unwrap();
+    assert_eq!(fs.session_start(), 0);
+    assert_eq!(read(&fs, "dir/c.txt").unwrap(), "first version\n");
+    assert!(read(&fs, "b.txt").is_some());
+    assert!(read(&fs, "d.txt").is_none());
+
+    let fs = ISO9660::new_session(File::open("multisession.iso").unwrap(), 48).unwrap();
+    assert_eq!(read(&fs, "dir/c.txt").unwrap(), "second version\n");
+    assert!(read(&fs, "b.txt").is_none());
+
+    // Files from earlier sessions are read by absolute address
+    let fs = ISO9660::new_last_session(File::open("multisession.iso").unwrap()).unwrap();
+    assert_eq!(fs.session_start(), 96);
+    assert_eq!(read(&fs, "a.txt").unwrap(), "unchanged\n");
+    assert_eq!(read(&fs, "dir/c.txt").unwrap(), "second version\n");
+    assert_eq!(read(&fs, "e.txt").unwrap(), "added in the third session\n");
+
+    let mut file = File::open("test.iso").unwrap();
+    assert_eq!(ISO9660::find_sessions(&mut file).unwrap(), [0]);
+
+    // Sessions of CDs don't start on a 16 sector boundary
+    let mut file = File::open("unaligned.iso").unwrap();
+    assert_eq!(ISO9660::find_sessions(&mut file).unwrap(), [0, 33]);
+    let fs = ISO9660::new_last_session(file).unwrap();
+    assert_eq!(read(&fs, "f.txt").unwrap(), "first session\n");
+    assert_eq!(read(&fs, "g.txt").unwrap(), "unaligned session\n");
+}
+
+#[test]
+fn test_session_history() {
+    let fs = ISO9660::new_last_session(File::open("multisession.iso").unwrap()).unwrap();
+    let sessions = fs.sessions().unwrap();
+    assert_eq!(sessions.len(), 3);
+    assert_eq!(
+        sessions
+            .iter()
+            .map(|x| x.session_start())
+            .collect::<Vec<_>>(),
+        [0, 48, 96]
+    );
+    assert_eq!(sessions[0].volume_identifier(), "TEST");
+    assert_eq!(sessions[0].creation_time().year(), 2020);
+
+    // Each session has its own root
+    let names = |fs: &ISO9660<File>| {
+        fs.root
+            .contents()
+            .skip(2)
+            .map(|x| x.unwrap().identifier().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&sessions[0]), ["A.TXT", "B.TXT", "DIR"]);
+    assert_eq!(names(&sessions[2]), ["A.TXT", "DIR", "D.TXT", "E.TXT"]);
+
+    let changes = sessions[0].diff(&sessions[1]).unwrap();
+    assert_eq!(
+        changes,
+        [
+            Change::Removed("B.TXT".to_string()),
+            Change::Added("D.TXT".to_string()),
+            Change::Modified("DIR/C.TXT".to_string()),
+        ]
+    );
+    let changes = sessions[1].diff(&sessions[2]).unwrap();
+    assert_eq!(changes, [Change::Added("E.TXT".to_string())]);
+    assert!(sessions[2].diff(&fs).unwrap().is_empty());
+}
+
+#[test]
+fn test_offset() {
+    let mut file = File::open("mbr.img").unwrap();
+    assert!(ISO9660::new(File::open("mbr.img").unwrap()).is_err());
+    let offsets = ISO9660::probe(&mut file).unwrap();
+    assert_eq!(offsets, [63 * 512]);
+    let fs = ISO9660::new_at_offset(file, offsets[0]).unwrap();
+    assert!(fs.joliet_root.is_some());
+    let mut text = String::new();
+    match fs.open("program_/readme.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+        _ => panic!("Not a file"),
+    };
+    assert_eq!(text, "Hello from Windows\r\n");
+
+    let mut file = File::open("gpt.img").unwrap();
+    let offsets = ISO9660::probe(&mut file).unwrap();
+    assert_eq!(offsets, [34 * 512]);
+    let fs = ISO9660::new_at_offset(file, offsets[0]).unwrap();
+    assert!(fs.open("attrs.txt").unwrap().is_some());
+
+    // A partition table claiming huge entries is ignored, but the scan still
+    // finds the filesystem
+    let mut image = std::fs::read("gpt.img").unwrap();
+    image[512 + 80..512 + 88].copy_from_slice(&[0, 4, 0, 0, 0xff, 0xff, 0xff, 0x7f]);
+    let offsets = ISO9660::probe(&mut Cursor::new(image)).unwrap();
+    assert_eq!(offsets, [34 * 512]);
+
+    let mut file = File::open("test.iso").unwrap();
+    assert_eq!(ISO9660::probe(&mut file).unwrap(), [0]);
+}
+
+#[test]
+fn test_raw_sectors() {
+    for (path, layout, subchannel) in [
+        ("mode1.bin", SectorLayout::Raw2352, false),
+        ("mode2sub.bin", SectorLayout::Raw2352, true),
+        ("mode2.bin", SectorLayout::Raw2336, false),
+        ("xar.iso", SectorLayout::Cooked, false),
+    ] {
+        let reader = RawSectorReader::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(reader.layout(), layout);
+        assert_eq!(reader.has_subchannel(), subchannel);
+
+        let fs = ISO9660::new(reader).unwrap();
+        let mut text = String::new();
+        match fs.open("attrs.txt").unwrap().unwrap() {
+            DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+            _ => panic!("Not a file"),
+        };
+        assert_eq!(text, "data after the record\n");
+    }
+}
+
+#[test]
+fn test_cue() {
+    let disc = Disc::open_cue("disc.cue").unwrap();
+    assert_eq!(disc.files().len(), 2);
+    let tracks = disc
+        .tracks()
+        .iter()
+        .map(|x| {
+            (
+                x.number,
+                x.mode,
+                x.file,
+                x.offset,
+                x.start,
+                x.pregap,
+                x.stored_pregap,
+                x.length,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tracks,
+        [
+            (1, TrackMode::Mode1(2352), 0, 0, 0, 0, 0, 24),
+            (2, TrackMode::Audio, 1, 10 * 2352, 34, 10, 10, 30),
+            (3, TrackMode::Audio, 1, 40 * 2352, 69, 5, 0, 20),
+        ]
+    );
+    assert!(disc.open_track(2).is_err());
+
+    let fs = ISO9660::new(disc.open_data_track().unwrap()).unwrap();
+    let mut text = String::new();
+    match fs.open("attrs.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+        _ => panic!("Not a file"),
+    };
+    assert_eq!(text, "data after the record\n");
+
+    // Sector 0 is the INDEX 01 of the first track, after its stored pregap
+    let disc = Disc::open_cue("pregap.cue").unwrap();
+    let track = &disc.tracks()[0];
+    assert_eq!(
+        (track.offset, track.start, track.pregap, track.stored_pregap),
+        (150 * 2352, 0, 150, 150)
+    );
+    let fs = ISO9660::new(disc.open_data_track().unwrap()).unwrap();
+    assert!(fs.open("attrs.txt").unwrap().is_some());
+
+    // The data track of the second session follows the lead-out and lead-in
+    let disc = Disc::open_cue("sessions.cue").unwrap();
+    let tracks = disc
+        .tracks()
+        .iter()
+        .map(|x| (x.number, x.session, x.start, x.length))
+        .collect::<Vec<_>>();
+    assert_eq!(tracks, [(1, 1, 0, 60), (2, 2, 60 + 11400, 22)]);
+    assert_eq!(disc.session_starts(), [60 + 11400]);
+    let fs = ISO9660::new_track(disc.open_data_track().unwrap()).unwrap();
+    let mut text = String::new();
+    match fs.open("track2.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+        _ => panic!("Not a file"),
+    };
+    assert_eq!(text, "second session\n");
+
+    // A lead-out given by the sheet replaces the standard one
+    let text = std::fs::read_to_string("sessions.cue").unwrap();
+    let text = text.replace("REM SESSION 02", "REM LEAD-OUT 00:30:00\nREM SESSION 02");
+    let tracks = CueSheet::parse(&text)
+        .unwrap()
+        .tracks(&[60 * 2352, 22 * 2352])
+        .unwrap();
+    assert_eq!(tracks[1].start, 60 + 2250 + 4500 + 150);
+
+    for time in ["99999999:00:00", "00:60:00", "00:00:75"] {
+        let text = format!("FILE \"a.bin\" BINARY\nTRACK 01 AUDIO\nINDEX 01 {}\n", time);
+        assert!(CueSheet::parse(&text).is_err());
+    }
+}
+
+#[test]
+fn test_error_correction() {
+    for path in ["mode1.bin", "mode2.bin"] {
+        let fs = ISO9660::new(RawSectorReader::new(File::open(path).unwrap()).unwrap()).unwrap();
+        assert!(fs.verify().unwrap().is_empty());
+    }
+
+    let mut image = std::fs::read("mode1.bin").unwrap();
+    let data = image
+        .windows(21)
+        .position(|x| x == b"data after the record")
+        .unwrap();
+    let last = image.len() / 2352 - 1;
+    // A single damaged byte can be repaired; a whole damaged sector can't
+    image[data] ^= 0x55;
+    for byte in &mut image[last * 2352 + 16..last * 2352 + 2064] {
+        *byte ^= 0xff;
+    }
+
+    let read_attrs = |fs: &ISO9660<RawSectorReader<Cursor<Vec<u8>>>>| {
+        let mut text = String::new();
+        match fs.open("attrs.txt").unwrap().unwrap() {
+            DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+            _ => panic!("Not a file"),
+        };
+        text
+    };
+
+    let reader = RawSectorReader::new(Cursor::new(image.clone())).unwrap();
+    let fs = ISO9660::new(reader).unwrap();
+    assert_ne!(read_attrs(&fs), "data after the record\n");
+    assert_eq!(
+        fs.verify().unwrap(),
+        [
+            BadSector {
+                lba: (data / 2352) as u64,
+                status: SectorStatus::Correctable,
+                path: Some("ATTRS.TXT".to_string()),
+            },
+            BadSector {
+                lba: last as u64,
+                status: SectorStatus::Uncorrectable,
+                path: Some("PLAIN.TXT".to_string()),
+            },
+        ]
+    );
+
+    let mut reader = RawSectorReader::new(Cursor::new(image.clone())).unwrap();
+    reader.set_error_correction(true);
+    let fs = ISO9660::new(reader).unwrap();
+    assert_eq!(read_attrs(&fs), "data after the record\n");
+
+    // Sectors past the end of a truncated image are reported as missing
+    image.truncate(last * 2352 + 1000);
+    let fs = ISO9660::new(RawSectorReader::new(Cursor::new(image)).unwrap()).unwrap();
+    assert_eq!(
+        fs.verify().unwrap().last(),
+        Some(&BadSector {
+            lba: last as u64,
+            status: SectorStatus::Missing,
+            path: Some("PLAIN.TXT".to_string()),
+        })
+    );
+}
+
+#[test]
+fn test_audio_tracks() {
+    let disc = Disc::open_cue("disc.cue").unwrap();
+    let numbers = disc.audio_tracks().map(|x| x.number).collect::<Vec<_>>();
+    assert_eq!(numbers, [2, 3]);
+    assert!(disc.open_audio_track(1, false).is_err());
+
+    let pcm = std::fs::read("audio.bin").unwrap();
+    let read_wav = |number, pregap| {
+        let mut wav = Vec::new();
+        let mut reader = disc.open_audio_track(number, pregap).unwrap();
+        reader.read_to_end(&mut wav).unwrap();
+        assert_eq!(wav.len() as u64, reader.size());
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav[40..44], (wav.len() as u32 - 44).to_le_bytes());
+        wav.split_off(44)
+    };
+
+    assert_eq!(read_wav(2, false), &pcm[10 * 2352..40 * 2352]);
+    assert_eq!(read_wav(2, true), &pcm[..40 * 2352]);
+    assert_eq!(read_wav(3, false), &pcm[40 * 2352..]);
+    let track3 = read_wav(3, true);
+    assert!(track3[..5 * 2352].iter().all(|&x| x == 0));
+    assert_eq!(&track3[5 * 2352..], &pcm[40 * 2352..]);
+
+    let mut reader = disc.open_audio_track(3, false).unwrap();
+    let mut buf = [0; 4];
+    reader.seek(SeekFrom::End(-4)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, pcm[pcm.len() - 4..]);
+}
+
+#[test]
+fn test_xa() {
+    let reader = RawSectorReader::new(File::open("xa.bin").unwrap()).unwrap();
+    let fs = ISO9660::new(reader).unwrap();
+
+    let text = match fs.open("readme.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file,
+        _ => panic!("Not a file"),
+    };
+    let xa = text.xa().unwrap();
+    assert_eq!((xa.group_id, xa.user_id, xa.file_number), (10, 20, 0));
+    assert!(xa.attributes.contains(XaAttributes::MODE2));
+    assert!(!xa.attributes.contains(XaAttributes::MODE2_FORM2));
+    let mut data = String::new();
+    text.read().read_to_string(&mut data).unwrap();
+    assert_eq!(data, "Form 1 text\n");
+
+    let stream = match fs.open("video.str").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file,
+        _ => panic!("Not a file"),
+    };
+    let xa = stream.xa().unwrap();
+    assert_eq!(xa.file_number, 1);
+    assert!(xa
+        .attributes
+        .contains(XaAttributes::MODE2_FORM2 | XaAttributes::INTERLEAVED));
+    assert_eq!(stream.size(), 2 * 2324);
+    let mut data = Vec::new();
+    stream.read().read_to_end(&mut data).unwrap();
+    let expected = (0..2u32)
+        .flat_map(|k| (0..2324).map(move |i| ((k * 7 + i) % 251) as u8))
+        .collect::<Vec<_>>();
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn test_nrg() {
+    let disc = Disc::open_nrg("disc.nrg").unwrap();
+    let tracks = disc
+        .tracks()
+        .iter()
+        .map(|x| {
+            (
+                x.number,
+                x.session,
+                x.mode,
+                x.offset,
+                x.start,
+                x.stored_pregap,
+                x.length,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tracks,
+        [
+            (1, 1, TrackMode::Mode1(2352), 0, 0, 0, 24),
+            (2, 1, TrackMode::Audio, 34 * 2352, 34, 10, 50),
+        ]
+    );
+    let mut wav = Vec::new();
+    disc.open_audio_track(2, false)
+        .unwrap()
+        .read_to_end(&mut wav)
+        .unwrap();
+    assert_eq!(wav[44..], std::fs::read("audio.bin").unwrap()[10 * 2352..]);
+
+    for path in ["disc.nrg", "tao.nrg"] {
+        let disc = Disc::open_nrg(path).unwrap();
+        let fs = ISO9660::new(disc.open_data_track().unwrap()).unwrap();
+        let mut text = String::new();
+        match fs.open("attrs.txt").unwrap().unwrap() {
+            DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+            _ => panic!("Not a file"),
+        };
+        assert_eq!(text, "data after the record\n");
+    }
+
+    // A DAO chunk numbering its tracks past 99 is rejected
+    let mut dao = vec![0; 22];
+    dao[20] = 255;
+    for _ in 0..2 {
+        let mut entry = vec![0; 42];
+        entry[12..14].copy_from_slice(&2352u16.to_be_bytes());
+        entry[14] = 0x07;
+        dao.extend(entry);
+    }
+    let mut image = b"DAOX".to_vec();
+    image.extend((dao.len() as u32).to_be_bytes());
+    image.extend(dao);
+    image.extend(b"END!\0\0\0\0NER5");
+    image.extend(0u64.to_be_bytes());
+    let path = std::env::temp_dir().join("iso9660-tracks.nrg");
+    std::fs::write(&path, image).unwrap();
+    assert!(Disc::open_nrg(&path).is_err());
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(
+        Disc::open_nrg("tao.nrg").unwrap().tracks()[0].mode,
+        TrackMode::Mode1(2048)
+    );
+    assert!(Disc::open_nrg("test.iso").is_err());
+}
+
+#[test]
+fn test_mds_ccd() {
+    let disc = Disc::open_mds("cdextra.mds").unwrap();
+    assert_eq!(disc.files()[0].to_str(), Some("cdextra.mdf"));
+    let tracks = disc
+        .tracks()
+        .iter()
+        .map(|x| {
+            (
+                x.number, x.session, x.mode, x.offset, x.start, x.pregap, x.length,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tracks,
+        [
+            (1, 1, TrackMode::Audio, 0, 0, 150, 30),
+            (2, 2, TrackMode::Mode1(2352), 30 * 2352, 48, 150, 22),
+        ]
+    );
+    assert_eq!(disc.session_starts(), [48]);
+    // The filesystem starts with the track, in the second session
+    assert!(ISO9660::new(disc.open_data_track().unwrap()).is_err());
+    let fs = ISO9660::new_track(disc.open_data_track().unwrap()).unwrap();
+    let mut text = String::new();
+    match fs.open("track2.txt").unwrap().unwrap() {
+        DirectoryEntry::File(file) => file.read().read_to_string(&mut text).unwrap(),
+        _ => panic!("Not a file"),
+    };
+    assert_eq!(text, "second session\n");
+    // Only the sectors of the track are checked
+    assert_eq!(fs.verify().unwrap(), []);
+
+    let disc = Disc::open_ccd("clone.ccd").unwrap();
+    assert_eq!(disc.files()[0].to_str(), Some("clone.img"));
+    let tracks = disc
+        .tracks()
+        .iter()
+        .map(|x| {
+            (
+                x.number,
+                x.mode,
+                x.offset,
+                x.start,
+                x.stored_pregap,
+                x.length,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tracks,
+        [
+            (1, TrackMode::Mode1(2352), 0, 0, 0, 24),
+            (2, TrackMode::Audio, 34 * 2352, 34, 10, 50),
+        ]
+    );
+    let fs = ISO9660::new(disc.open_data_track().unwrap()).unwrap();
+    assert!(fs.open("attrs.txt").unwrap().is_some());
+}
+
+#[test]
+fn test_cso() {
+    for (path, compression) in [
+        ("test.cso", Compression::Deflate),
+        ("test.zso", Compression::Lz4),
+    ] {
+        let reader = CsoReader::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(reader.compression(), compression);
+        assert_eq!(reader.size(), 815104);
+        let fs = ISO9660::new(reader).unwrap();
+
+        let dir = match fs.open("a/b/c").unwrap().unwrap() {
+            DirectoryEntry::Directory(dir) => dir,
+            _ => panic!("Not a directory"),
+        };
+        assert_eq!(dir.contents().map(Result::unwrap).count(), 202);
+
+        // Spans compressed and stored blocks
+        let file = match fs.open("gpl_3_0.txt").unwrap().unwrap() {
+            DirectoryEntry::File(file) => file,
+            _ => panic!("Not a file"),
+        };
+        let mut text = String::new();
+        file.read().read_to_string(&mut text).unwrap();
+        let hash = md5::compute(text);
+        assert_eq!(format!("{:x}", hash), "1ebbd3e34237af26da5dc08a4e440464");
+    }
+
+    // Reads match the uncompressed image, across block boundaries
+    let mut reader = CsoReader::new(File::open("test.zso").unwrap()).unwrap();
+    let mut image = Vec::new();
+    File::open("test.iso")
+        .unwrap()
+        .read_to_end(&mut image)
+        .unwrap();
+    let mut buf = vec![0; 5000];
+    reader.seek(SeekFrom::Start(100_000)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, &image[100_000..105_000]);
+    reader.seek(SeekFrom::End(-10)).unwrap();
+    assert_eq!(reader.read(&mut buf).unwrap(), 10);
+}